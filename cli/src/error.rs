@@ -20,7 +20,7 @@ make sure it's started first using the following command in a separate process:
 #[derive(Debug)]
 pub enum CliError {
   CompilationError(CompilerError),
-  ConfigurationSetError(toml::ser::Error),
+  ConfigurationSetError(ConfigError),
   ConfigurationDeleteError(ConfigError),
   BlockchainError(NodeError),
   BlockchainConnectorError(ConnectionError),
@@ -62,7 +62,7 @@ OPTIONS can also be specified in the project's vibranium.toml file:
           _ => write!(f, "{}", error),
         }
       },
-      CliError::ConfigurationSetError(error) => write!(f, "Couldn't set configuration: {}", error),
+      CliError::ConfigurationSetError(error) => write!(f, "{}", error),
       CliError::ConfigurationDeleteError(_error) => write!(f, "Cannot delete config array or object option that isn't empty"),
       CliError::BlockchainError(error) => {
         match error {
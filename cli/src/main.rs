@@ -15,9 +15,11 @@ use clap::{App, SubCommand, Arg};
 
 use vibranium::Vibranium;
 use vibranium::blockchain;
+use vibranium::config::VIBRANIUM_CONFIG_FILE;
 use vibranium::deployment;
 use vibranium::deployment::DeployOptions;
-use vibranium::compiler::CompilerConfig;
+use vibranium::compiler::{CompilerConfig, CompilerOutput};
+use vibranium::compiler::watch::WatchConfig;
 use vibranium::project_generator::ResetOptions;
 
 mod error;
@@ -51,6 +53,12 @@ fn run() -> Result<(), Error> {
                       .value_name("PATH")
                       .help("Specifies path to Vibranium project from which to spin up a node")
                       .takes_value(true))
+                    .arg(Arg::with_name("env")
+                      .short("e")
+                      .long("env")
+                      .value_name("ENVIRONMENT")
+                      .help("Specifies the environment defined in vibranium.toml to use")
+                      .takes_value(true))
                     .arg(Arg::with_name("client-opts")
                       .value_name("OPTIONS")
                       .help("Specifies node specific options that will be passed down to the client")
@@ -103,11 +111,16 @@ fn run() -> Result<(), Error> {
                       .value_name("PATH")
                       .help("Specifies path to Vibranium project")
                       .takes_value(true))
-                    .arg(Arg::with_name("set")
-                      .number_of_values(2)
+                    .arg(Arg::with_name("option")
+                      .min_values(1)
+                      .max_values(2)
                       .value_names(&["CONFIG_OPTION", "VALUE"])
-                      .help("Sets a configuration value")
+                      .help("Reads a configuration value, or sets it if VALUE is given")
                       .takes_value(true))
+                    .arg(Arg::with_name("list")
+                      .short("l")
+                      .long("list")
+                      .help("Lists all configuration options and their values"))
                     .arg(Arg::with_name("unset")
                       .short("u")
                       .long("unset")
@@ -118,6 +131,24 @@ fn run() -> Result<(), Error> {
                       .short("v")
                       .long("verbose")
                       .help("Generates verbose output"))
+                    .subcommand(SubCommand::with_name("check")
+                      .about("Checks the configuration of a Vibranium project for problems")
+                      .arg(Arg::with_name("path")
+                        .short("p")
+                        .long("path")
+                        .value_name("PATH")
+                        .help("Specifies path to Vibranium project")
+                        .takes_value(true))
+                    )
+                    .subcommand(SubCommand::with_name("migrate")
+                      .about("Migrates the configuration of a Vibranium project to the latest version")
+                      .arg(Arg::with_name("path")
+                        .short("p")
+                        .long("path")
+                        .value_name("PATH")
+                        .help("Specifies path to Vibranium project")
+                        .takes_value(true))
+                    )
                   )
                   .subcommand(SubCommand::with_name("compile")
                     .about("Compiles Smart Contracts from Vibranium project")
@@ -133,6 +164,12 @@ fn run() -> Result<(), Error> {
                       .value_name("PATH")
                       .help("Specifies path to Vibranium project to compile")
                       .takes_value(true))
+                    .arg(Arg::with_name("env")
+                      .short("e")
+                      .long("env")
+                      .value_name("ENVIRONMENT")
+                      .help("Specifies the environment defined in vibranium.toml to use")
+                      .takes_value(true))
                     .arg(Arg::with_name("compiler-opts")
                       .value_name("OPTIONS")
                       .help("Specifies compiler specific options that will be passed down to the compiler")
@@ -142,6 +179,14 @@ fn run() -> Result<(), Error> {
                       .short("nsi")
                       .long("no-smart-imports")
                       .help("Turns of smart import support in Solidity source files"))
+                    .arg(Arg::with_name("force")
+                      .short("f")
+                      .long("force")
+                      .help("Compiles all Smart Contracts, even if they haven't changed since the last compilation"))
+                    .arg(Arg::with_name("watch")
+                      .short("w")
+                      .long("watch")
+                      .help("Recompiles Smart Contracts whenever they or the files they import change"))
                     .arg(Arg::with_name("verbose")
                       .short("v")
                       .long("verbose")
@@ -155,6 +200,12 @@ fn run() -> Result<(), Error> {
                       .value_name("PATH")
                       .help("Specifies path to Vibranium project")
                       .takes_value(true))
+                    .arg(Arg::with_name("env")
+                      .short("e")
+                      .long("env")
+                      .value_name("ENVIRONMENT")
+                      .help("Specifies the environment defined in vibranium.toml to use")
+                      .takes_value(true))
                   )
                   .subcommand(SubCommand::with_name("deploy")
                     .about("Deploys compiled artifacts")
@@ -164,6 +215,12 @@ fn run() -> Result<(), Error> {
                       .value_name("PATH")
                       .help("Specifies path to Vibranium project")
                       .takes_value(true))
+                    .arg(Arg::with_name("env")
+                      .short("e")
+                      .long("env")
+                      .value_name("ENVIRONMENT")
+                      .help("Specifies the environment defined in vibranium.toml to use")
+                      .takes_value(true))
                     .arg(Arg::with_name("no-tracking")
                       .short("nt")
                       .long("no-tracking")
@@ -181,6 +238,12 @@ fn run() -> Result<(), Error> {
                       .value_name("PATH")
                       .help("Specifies path to Vibranium project")
                       .takes_value(true))
+                    .arg(Arg::with_name("env")
+                      .short("e")
+                      .long("env")
+                      .value_name("ENVIRONMENT")
+                      .help("Specifies the environment defined in vibranium.toml to use")
+                      .takes_value(true))
                   );
                     
 
@@ -196,7 +259,10 @@ fn run() -> Result<(), Error> {
     ("node", Some(cmd)) => {
       println!("Starting blockchain node...");
       let path = pathbuf_from_or_current_dir(cmd.value_of("path"))?;
-      let vibranium = Vibranium::new(path)?;
+      let mut vibranium = Vibranium::discover(path)?;
+      if let Some(environment) = cmd.value_of("env") {
+        vibranium.set_environment(environment.to_string());
+      }
 
       let client_options = cmd.values_of("client-opts").map(|options| {
         options.map(std::string::ToString::to_string).collect()
@@ -224,7 +290,7 @@ fn run() -> Result<(), Error> {
     ("reset", Some(cmd)) => {
       println!("Resetting Vibranium project...");
       let path = pathbuf_from_or_current_dir(cmd.value_of("path"))?;
-      let vibranium = Vibranium::new(path)?;
+      let vibranium = Vibranium::discover(path)?;
 
       vibranium.reset_project(ResetOptions {
         restore_config: cmd.is_present("restore-config"),
@@ -236,42 +302,75 @@ fn run() -> Result<(), Error> {
     },
 
     ("config", Some(cmd)) => {
+      if let Some(check_cmd) = cmd.subcommand_matches("check") {
+        let path = pathbuf_from_or_current_dir(check_cmd.value_of("path").or_else(|| cmd.value_of("path")))?;
+        let vibranium = Vibranium::discover(path)?;
+        let warnings = vibranium.check_config()?;
+        if warnings.is_empty() {
+          println!("No problems found in configuration.");
+        } else {
+          eprintln!("Found {} warning(s) in {}:", warnings.len(), VIBRANIUM_CONFIG_FILE);
+          for warning in warnings {
+            eprintln!("  {}", warning);
+          }
+        }
+        return Ok(());
+      }
+
+      if let Some(migrate_cmd) = cmd.subcommand_matches("migrate") {
+        let path = pathbuf_from_or_current_dir(migrate_cmd.value_of("path").or_else(|| cmd.value_of("path")))?;
+        let vibranium = Vibranium::discover(path)?;
+
+        match vibranium.migrate_config()? {
+          Some(summary) => {
+            println!("Migrated configuration from version {} to {}:", summary.from_version, summary.to_version);
+            for migration in summary.migrations {
+              println!("  - {}", migration);
+            }
+            println!("Backup of the previous configuration: {}", summary.backup_file.display());
+          },
+          None => println!("Configuration is up to date."),
+        }
+        return Ok(());
+      }
+
       let path = pathbuf_from_or_current_dir(cmd.value_of("path"))?;
-      let vibranium = Vibranium::new(path)?;
+      let vibranium = Vibranium::discover(path)?;
 
-      if let Some(options) = cmd.values_of("set") {
+      if let Some(options) = cmd.values_of("option") {
         let mut args: Vec<String> = options.map(std::string::ToString::to_string).collect();
         let config_option = args.remove(0);
-        let mut value_arg = args[0].to_owned(); 
-
-        let value = if is_multi_value_arg(&value_arg) {
-
-          remove_multi_value_delimitiers(&mut value_arg);
-          let values: Vec<String> = value_arg.split(',')
-            .map(str::trim)
-            .map(std::string::ToString::to_string)
-            .filter(|val| !std::string::String::is_empty(val))
-            .collect();
 
-          toml::value::Value::try_from(values)
-            .map_err(error::CliError::ConfigurationSetError)?
-        } else {
-          toml::value::Value::try_from(value_arg)
-            .map_err(error::CliError::ConfigurationSetError)?
-        };
+        if args.is_empty() {
+          let value = vibranium.get_config(config_option.clone())?
+            .ok_or_else(|| error::CliError::Other(format!("Couldn't find configuration option '{}'", config_option)))?;
+          println!("{}", format_config_value(&value));
+          return Ok(());
+        }
 
+        let value = vibranium::config::parse_value(&config_option, &args[0])
+          .map_err(error::CliError::ConfigurationSetError)?;
         vibranium.set_config(config_option, value)?
       }
 
       if let Some(config_option) = cmd.value_of("unset") {
         vibranium.unset_config(config_option.to_string()).map_err(error::CliError::ConfigurationDeleteError)?
       }
+
+      if cmd.is_present("list") {
+        for (config_option, value) in vibranium.list_config()? {
+          println!("{}={}", config_option, format_config_value(&value));
+        }
+      }
     },
 
     ("compile", Some(cmd)) => {
       println!("Compiling Vibranium project...");
       let path = pathbuf_from_or_current_dir(cmd.value_of("path"))?;
-      let vibranium = Vibranium::new(path)?;
+      let mut vibranium = Vibranium::discover(path)?;
+      if let Some(environment) = cmd.value_of("env") {
+        vibranium.set_environment(environment.to_string());
+      }
       
       let compiler_options = cmd.values_of("compiler-opts").map(|options| {
         options.map(std::string::ToString::to_string).collect()
@@ -281,24 +380,34 @@ fn run() -> Result<(), Error> {
         compiler: cmd.value_of("compiler").map(std::string::ToString::to_string),
         compiler_options,
         smart_imports_enabled: !cmd.is_present("no-smart-imports"),
+        force: cmd.is_present("force"),
       };
 
-      vibranium
-        .compile(config)
-        .map_err(error::CliError::CompilationError)
-        .and_then(|output| {
-          if !output.stderr.is_empty() {
-            io::stderr().write_all(&output.stderr).unwrap();
-          }
-          io::stdout().write_all(&output.stdout).unwrap();
-          println!("Done.");
-          Ok(())
-        })?
+      if cmd.is_present("watch") {
+        vibranium
+          .watch_compile(config, &WatchConfig::default(), |result| {
+            match result {
+              Ok(output) => print_compiler_output(&output),
+              Err(err) => eprintln!("{}\n", err),
+            }
+            println!("Watching for changes...");
+            true
+          })
+          .map_err(error::CliError::CompilationError)?
+      } else {
+        vibranium
+          .compile(config)
+          .map_err(error::CliError::CompilationError)
+          .map(|output| print_compiler_output(&output))?
+      }
     },
 
     ("accounts", Some(cmd)) => {
       let path = pathbuf_from_or_current_dir(cmd.value_of("path"))?;
-      let vibranium = Vibranium::new(path)?;
+      let mut vibranium = Vibranium::discover(path)?;
+      if let Some(environment) = cmd.value_of("env") {
+        vibranium.set_environment(environment.to_string());
+      }
       let accounts = vibranium.node_accounts()?;
 
       for (i, address) in accounts.iter().enumerate() {
@@ -309,7 +418,10 @@ fn run() -> Result<(), Error> {
     ("deploy", Some(cmd)) => {
       println!("Deploying...");
       let path = pathbuf_from_or_current_dir(cmd.value_of("path"))?;
-      let vibranium = Vibranium::new(path)?;
+      let mut vibranium = Vibranium::discover(path)?;
+      if let Some(environment) = cmd.value_of("env") {
+        vibranium.set_environment(environment.to_string());
+      }
 
       let deploy_options = DeployOptions {
         tracking_enabled: if cmd.is_present("no-tracking") {
@@ -347,7 +459,10 @@ fn run() -> Result<(), Error> {
 
     ("list", Some(cmd)) => {
       let path = pathbuf_from_or_current_dir(cmd.value_of("path"))?;
-      let vibranium = Vibranium::new(path)?;
+      let mut vibranium = Vibranium::discover(path)?;
+      if let Some(environment) = cmd.value_of("env") {
+        vibranium.set_environment(environment.to_string());
+      }
       let tracking_data = vibranium.get_tracking_data().map_err(|err| error::CliError::Other(err.to_string()))?;
 
       match tracking_data {
@@ -373,11 +488,34 @@ fn pathbuf_from_or_current_dir(path: Option<&str>) -> Result<PathBuf, std::io::E
   path.map(|p| Ok(PathBuf::from(p))).unwrap_or_else(env::current_dir)
 }
 
-fn is_multi_value_arg(value: &str) -> bool {
-  value.starts_with('[') && value.ends_with(']')
+fn format_config_value(value: &toml::Value) -> String {
+  match value {
+    toml::Value::String(value) => value.to_owned(),
+    _ => value.to_string(),
+  }
+}
+
+fn format_size(size: Option<usize>) -> String {
+  size.map(|size| format!("{} bytes", size)).unwrap_or_else(|| "unknown".to_string())
 }
 
-fn remove_multi_value_delimitiers(value: &mut String) {
-  value.remove(0);
-  value.pop();
+fn print_compiler_output(output: &CompilerOutput) {
+  // Compiler output is only shown as is, if it couldn't be parsed into diagnostics.
+  if !output.stderr.is_empty() && output.diagnostics.is_empty() {
+    io::stderr().write_all(&output.stderr).unwrap();
+  }
+  io::stdout().write_all(&output.stdout).unwrap();
+  for diagnostic in &output.diagnostics {
+    eprintln!("{}\n", diagnostic);
+  }
+  if !output.contract_sizes.is_empty() {
+    println!("Smart Contract sizes (runtime / initcode):");
+    for size in &output.contract_sizes {
+      println!("  {} ({}): {} / {}", size.contract_name, size.source_path, format_size(size.runtime_size), format_size(size.initcode_size));
+    }
+  }
+  if output.compiled_sources.is_empty() && !output.cached_sources.is_empty() {
+    println!("Nothing to compile. Use --force to compile unchanged Smart Contracts.");
+  }
+  println!("Done.");
 }
@@ -116,15 +116,12 @@ mod init_cmd {
 
     let config = read_config(&project_path)?;
     assert_eq!(config.sources.artifacts, "artifacts");
-    assert_eq!(config.sources.smart_contracts, vec!["contracts/*.sol"]);
+    assert_eq!(config.sources.smart_contracts, vec!["contracts/*.sol", "contracts/*.vy"]);
 
     let compiler_config = config.compiler.unwrap();
-    let compiler_options = compiler_config.options.unwrap();
 
     assert_eq!(&compiler_config.cmd.unwrap(), "solc");
-    assert_eq!(&compiler_options[0], "--abi");
-    assert_eq!(&compiler_options[1], "--bin");
-    assert_eq!(&compiler_options[2], "--overwrite");
+    assert_eq!(compiler_config.options.is_none(), true);
 
     let blockchain_config = config.blockchain.unwrap();
     let blockchain_options = blockchain_config.options;
@@ -321,12 +318,14 @@ mod reset_cmd {
 #[cfg(test)]
 mod config_cmd {
 
+  use std::fs;
   use std::process::Command;
   use assert_cmd::prelude::*;
   use predicates::prelude::*;
 
   use super::setup_vibranium_project;
   use super::set_configuration;
+  use super::set_configurations;
   use super::read_config;
 
   #[test]
@@ -384,16 +383,236 @@ mod config_cmd {
 
     cmd.assert()
         .failure()
-        .stderr(predicate::str::contains("Couldn't deserialize vibranium config"));
+        .stderr(predicate::str::contains("Couldn't set configuration option 'compiler.options': expected array of string"));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_set_config_values_according_to_their_type() -> Result<(), Box<dyn std::error::Error>> {
+
+    let (tmp_dir, project_path) = setup_vibranium_project(None)?;
+    set_configurations(vec![
+      ("deployment.smart_contracts", "[{ name = \"SimpleTestContract\" }]"),
+      ("deployment.tx_confirmations", "2"),
+      ("deployment.tracking_enabled", "false"),
+      ("deployment.smart_contracts.[0].args", "[{ value = \"1\", kind = \"uint\" }]"),
+    ], &project_path)?;
+
+    let config = read_config(&project_path)?;
+    let deployment = config.deployment.unwrap();
+    assert_eq!(deployment.tx_confirmations, Some(2));
+    assert_eq!(deployment.tracking_enabled, Some(false));
+    assert_eq!(deployment.smart_contracts[0].args.as_ref().unwrap()[0].kind, "uint");
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_preserve_comments_when_updating_config_file() -> Result<(), Box<dyn std::error::Error>> {
+
+    let (tmp_dir, project_path) = setup_vibranium_project(None)?;
+    let config_file = project_path.join("vibranium.toml");
+    let config_toml = format!("# Shared project settings\n{}", fs::read_to_string(&config_file)?);
+    fs::write(&config_file, &config_toml)?;
+
+    set_configuration("sources.artifacts", "foo", &project_path)?;
+
+    let updated_config_toml = fs::read_to_string(&config_file)?;
+    assert_eq!(updated_config_toml, config_toml.replace("artifacts = \"artifacts\"", "artifacts = \"foo\""));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_reject_config_options_that_do_not_exist() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, project_path) = setup_vibranium_project(None)?;
+    let config_file = project_path.join("vibranium.toml");
+    let contents = fs::read_to_string(&config_file)?;
+
+    let mut cmd = Command::main_binary()?;
+    cmd.arg("config")
+        .arg("unknown")
+        .arg("foo")
+        .arg("--path")
+        .arg(&project_path);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Couldn't set unknown configuration option 'unknown'"));
+
+    assert_eq!(fs::read_to_string(&config_file)?, contents);
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_read_config_option() -> Result<(), Box<dyn std::error::Error>> {
+
+    let (tmp_dir, project_path) = setup_vibranium_project(None)?;
+    set_configuration("sources.artifacts", "foo", &project_path)?;
+
+    let mut cmd = Command::main_binary()?;
+    cmd.arg("config")
+        .arg("sources.artifacts")
+        .arg("--path")
+        .arg(&project_path);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::similar("foo\n"));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_check_valid_config() -> Result<(), Box<dyn std::error::Error>> {
+
+    let (tmp_dir, project_path) = setup_vibranium_project(None)?;
+
+    let mut cmd = Command::main_binary()?;
+    cmd.arg("config")
+        .arg("check")
+        .arg("--path")
+        .arg(&project_path);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("No problems found in configuration."));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_report_config_problems_with_line() -> Result<(), Box<dyn std::error::Error>> {
+
+    let (tmp_dir, project_path) = setup_vibranium_project(None)?;
+    let config_file = project_path.join("vibranium.toml");
+    let config = fs::read_to_string(&config_file)?;
+    fs::write(&config_file, config.replace("[compiler]\n", "[compiler]\nmax_contract_size = \"large\"\n"))?;
+
+    let mut cmd = Command::main_binary()?;
+    cmd.arg("config")
+        .arg("check")
+        .arg("--path")
+        .arg(&project_path);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Found 1 problem(s) in vibranium.toml"))
+        .stderr(predicate::str::contains("line 8: compiler.max_contract_size: expected integer but got string"));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_report_unknown_config_keys_as_warnings() -> Result<(), Box<dyn std::error::Error>> {
+
+    let (tmp_dir, project_path) = setup_vibranium_project(None)?;
+    let config_file = project_path.join("vibranium.toml");
+    let config = fs::read_to_string(&config_file)?;
+    fs::write(&config_file, config.replace("[sources]\n", "[sources]\nartifacts_dir = \"foo\"\n"))?;
+
+    let mut cmd = Command::main_binary()?;
+    cmd.arg("config")
+        .arg("check")
+        .arg("--path")
+        .arg(&project_path);
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Found 1 warning(s) in vibranium.toml"))
+        .stderr(predicate::str::contains("line 4: sources.artifacts_dir: unknown key"));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_migrate_config_and_keep_backup() -> Result<(), Box<dyn std::error::Error>> {
+
+    let (tmp_dir, project_path) = setup_vibranium_project(None)?;
+    let config_file = project_path.join("vibranium.toml");
+    let legacy_config = "[sources]\nartifacts = \"artifacts\"\nsmart_contracts = [\"contracts/*.sol\"]\n";
+    fs::write(&config_file, legacy_config)?;
+
+    let mut cmd = Command::main_binary()?;
+    cmd.arg("config")
+        .arg("migrate")
+        .arg("--path")
+        .arg(&project_path);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Migrated configuration from version 0 to 2"));
+
+    assert_eq!(fs::read_to_string(project_path.join("vibranium.toml.v0.bak"))?, legacy_config);
+    assert_eq!(read_config(&project_path)?.version, Some(2));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_discover_project_from_subdirectory() -> Result<(), Box<dyn std::error::Error>> {
+
+    let (tmp_dir, project_path) = setup_vibranium_project(None)?;
+    set_configuration("sources.artifacts", "foo", &project_path)?;
+
+    let mut cmd = Command::main_binary()?;
+    cmd.arg("config")
+        .arg("sources.artifacts")
+        .current_dir(project_path.join("contracts"));
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::similar("foo\n"));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_fail_when_reading_config_option_that_does_not_exist()-> Result<(), Box<dyn std::error::Error>> {
+
+    let (tmp_dir, project_path) = setup_vibranium_project(None)?;
+
+    let mut cmd = Command::main_binary()?;
+    cmd.arg("config")
+        .arg("sources.unknown")
+        .arg("--path")
+        .arg(&project_path);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Couldn't find configuration option 'sources.unknown'"));
 
     tmp_dir.close()?;
     Ok(())
   }
 
   #[test]
-  fn it_should_ignore_config_options_that_do_not_exist() -> Result<(), Box<dyn std::error::Error>> {
+  fn it_should_list_config_options() -> Result<(), Box<dyn std::error::Error>> {
+
     let (tmp_dir, project_path) = setup_vibranium_project(None)?;
-    set_configuration("unknown", "foo", &project_path)?;
+
+    let mut cmd = Command::main_binary()?;
+    cmd.arg("config")
+        .arg("--list")
+        .arg("--path")
+        .arg(&project_path);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("sources.artifacts=artifacts\n"))
+        .stdout(predicate::str::contains("sources.smart_contracts=[\"contracts/*.sol\", \"contracts/*.vy\"]\n"));
+
     tmp_dir.close()?;
     Ok(())
   }
@@ -626,6 +845,7 @@ mod accounts_cmd {
 #[cfg(test)]
 mod deploy_cmd {
 
+  use std::fs;
   use std::process::Command;
   use assert_cmd::prelude::*;
   use predicates::prelude::*;
@@ -658,6 +878,43 @@ mod deploy_cmd {
     Ok(())
   }
 
+  #[test]
+  fn it_should_fail_if_environment_does_not_exist() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, project_path) = setup_vibranium_project(None)?;
+
+    let mut cmd = Command::main_binary()?;
+    cmd.arg("deploy")
+        .arg("--path")
+        .arg(&project_path)
+        .arg("--env")
+        .arg("staging");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Couldn't find configuration for environment 'staging'"));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_fail_if_configuration_is_invalid() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, project_path) = setup_vibranium_project(None)?;
+    set_configuration("blockchain.connector.protocol", "ipc", &project_path)?;
+
+    let mut cmd = Command::main_binary()?;
+    cmd.arg("deploy")
+        .arg("--path")
+        .arg(&project_path);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("blockchain.connector.protocol: unsupported protocol 'ipc'"));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
   #[test]
   fn it_should_skip_deployment_if_no_artifacts_exist() -> Result<(), Box<dyn std::error::Error>> {
 
@@ -807,7 +1064,7 @@ mod deploy_cmd {
 
     cmd.assert()
         .failure()
-        .stderr(predicate::str::contains("Couldn't read Smart Contract constructor parameter"));
+        .stderr(predicate::str::contains("deployment.smart_contracts.[0].args.[0].kind: invalid kind 'invalid'"));
 
     tmp_dir.close()?;
     Ok(())
@@ -985,6 +1242,52 @@ mod deploy_cmd {
     Ok(())
   }
 
+  #[test]
+  fn it_should_deploy_with_unknown_config_keys() -> Result<(), Box<dyn std::error::Error>> {
+
+    let mut config = ProjectConfig::default();
+
+    config.deployment = Some(ProjectDeploymentConfig {
+      gas_limit: None,
+      gas_price: None,
+      tx_confirmations: None,
+      tracking_enabled: None,
+      smart_contracts: vec![
+        SmartContractConfig {
+          name: "SimpleTestContract".to_string(),
+          address: None,
+          instance_of: None,
+          args: Some(vec![
+            SmartContractArg { value: "200".to_string(),kind: "uint".to_string() },
+          ]),
+          gas_limit: None,
+          gas_price: None,
+          abi_path: None,
+          bytecode_path: None,
+        },
+      ],
+    });
+
+    let (tmp_dir, project_path) = setup_vibranium_project(Some(config))?;
+    let config_file = project_path.join("vibranium.toml");
+    let mut config = fs::read_to_string(&config_file)?;
+    config.push_str("\n[custom]\nteam = \"tokens\"\n");
+    fs::write(&config_file, config)?;
+
+    create_test_artifact(&project_path, "SimpleTestContract.abi")?;
+    create_test_artifact(&project_path, "SimpleTestContract.bin")?;
+
+    let mut cmd = Command::main_binary()?;
+    cmd.arg("deploy")
+        .arg("--path")
+        .arg(&project_path);
+
+    cmd.assert().success();
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
   #[test]
   fn it_should_deploy_multiple_smart_contracts() -> Result<(), Box<dyn std::error::Error>> {
 
@@ -40,15 +40,15 @@ impl<'a> Node<'a> {
       Some(options) => {
         match client.parse() {
           Ok(SupportedBlockchainClients::Parity) => utils::merge_cli_options(
-            support::default_options_from(SupportedBlockchainClients::Parity, &self.config.vibranium_dir_path),
+            support::default_options_from(SupportedBlockchainClients::Parity, &self.config.vibranium_dir_path, self.config.environment()),
             options.to_vec()
           ),
           Ok(SupportedBlockchainClients::Geth) => utils::merge_cli_options(
-            support::default_options_from(SupportedBlockchainClients::Geth, &self.config.vibranium_dir_path),
+            support::default_options_from(SupportedBlockchainClients::Geth, &self.config.vibranium_dir_path, self.config.environment()),
             options.to_vec()
           ),
           Ok(SupportedBlockchainClients::Ganache) => utils::merge_cli_options(
-            support::default_options_from(SupportedBlockchainClients::Ganache, &self.config.vibranium_dir_path),
+            support::default_options_from(SupportedBlockchainClients::Ganache, &self.config.vibranium_dir_path, self.config.environment()),
             options.to_vec()
           ),
           Err(_err) => options.to_vec(),
@@ -56,8 +56,8 @@ impl<'a> Node<'a> {
       }
       None => {
         match project_config.blockchain {
-          Some(config) => config.options.unwrap_or_else(|| try_default_options_from(&client, &self.config.vibranium_dir_path)),
-          None => try_default_options_from(&client, &self.config.vibranium_dir_path)
+          Some(config) => config.options.unwrap_or_else(|| try_default_options_from(&client, &self.config.vibranium_dir_path, self.config.environment())),
+          None => try_default_options_from(&client, &self.config.vibranium_dir_path, self.config.environment())
         }
       }
     };
@@ -80,11 +80,11 @@ impl<'a> Node<'a> {
 }
 
 
-fn try_default_options_from(client: &str, vibranium_dir_path: &PathBuf) -> Vec<String> {
+fn try_default_options_from(client: &str, vibranium_dir_path: &PathBuf, environment: &str) -> Vec<String> {
   match client.parse() {
-    Ok(SupportedBlockchainClients::Parity) => support::default_options_from(SupportedBlockchainClients::Parity, vibranium_dir_path),
-    Ok(SupportedBlockchainClients::Geth) => support::default_options_from(SupportedBlockchainClients::Geth, vibranium_dir_path),
-    Ok(SupportedBlockchainClients::Ganache) => support::default_options_from(SupportedBlockchainClients::Ganache, vibranium_dir_path),
+    Ok(SupportedBlockchainClients::Parity) => support::default_options_from(SupportedBlockchainClients::Parity, vibranium_dir_path, environment),
+    Ok(SupportedBlockchainClients::Geth) => support::default_options_from(SupportedBlockchainClients::Geth, vibranium_dir_path, environment),
+    Ok(SupportedBlockchainClients::Ganache) => support::default_options_from(SupportedBlockchainClients::Ganache, vibranium_dir_path, environment),
     Err(_err) => vec![],
   }
 }
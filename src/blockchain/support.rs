@@ -8,7 +8,6 @@ use std::string::ToString;
 use crate::project_generator;
 use project_generator::{
   DEFAULT_DATADIR_NAME,
-  DEFAULT_ENVIRONMENT,
   DEFAULT_DEV_PASSWORDS_DIR,
 };
 
@@ -97,7 +96,7 @@ pub fn init_node(client: &str, options: &[String], vibranium_dir_path: &PathBuf)
   }
 }
 
-pub fn default_options_from(client: SupportedBlockchainClients, vibranium_dir_path: &PathBuf) -> Vec<String> {
+pub fn default_options_from(client: SupportedBlockchainClients, vibranium_dir_path: &PathBuf, environment: &str) -> Vec<String> {
   match client {
     SupportedBlockchainClients::Parity => {
       vec![
@@ -108,7 +107,7 @@ pub fn default_options_from(client: SupportedBlockchainClients, vibranium_dir_pa
         "--base-path".to_string(),
         vibranium_dir_path
           .join(DEFAULT_DATADIR_NAME)
-          .join(DEFAULT_ENVIRONMENT)
+          .join(environment)
           .to_string_lossy()
           .to_string(),
         "--unlock".to_string(),
@@ -131,7 +130,7 @@ pub fn default_options_from(client: SupportedBlockchainClients, vibranium_dir_pa
         "--datadir".to_string(),
         vibranium_dir_path
           .join(DEFAULT_DATADIR_NAME)
-          .join(DEFAULT_ENVIRONMENT)
+          .join(environment)
           .to_string_lossy()
           .to_string(),
       ]
@@ -142,7 +141,7 @@ pub fn default_options_from(client: SupportedBlockchainClients, vibranium_dir_pa
         "--db".to_string(),
         vibranium_dir_path
           .join(DEFAULT_DATADIR_NAME)
-          .join(DEFAULT_ENVIRONMENT)
+          .join(environment)
           .to_string_lossy()
           .to_string(),
       ]
@@ -0,0 +1,269 @@
+use super::error::CompilerError;
+use super::standard_json::{StandardJsonContract, StandardJsonLinkReference};
+
+use serde_json;
+use sha3::{Digest, Sha3_256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const ARTIFACT_EXTENSION_JSON: &str = "json";
+
+/// Separates the contract name from the source path in names of disambiguated artifacts.
+/// It's not valid in contract names, so disambiguated names never clash with plain ones.
+pub const QUALIFIED_NAME_SEPARATOR: char = '-';
+
+/// A compiled Smart Contract together with the metadata of its build, written to
+/// `<ContractName>.json` in the artifacts directory.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ContractArtifact {
+  #[serde(rename = "contractName")]
+  pub contract_name: String,
+  /// The source file the contract is defined in, relative to the project directory
+  /// unless it's outside of it.
+  #[serde(rename = "sourcePath")]
+  pub source_path: String,
+  /// SHA3-256 hash of the source file's content at the time it was compiled.
+  #[serde(rename = "sourceHash")]
+  pub source_hash: String,
+  pub compiler: ArtifactCompiler,
+  pub abi: serde_json::Value,
+  /// Hex encoded creation bytecode, as written to `<ContractName>.bin`.
+  pub bytecode: String,
+  /// Hex encoded runtime bytecode. Empty if the compiler doesn't report it.
+  #[serde(rename = "deployedBytecode")]
+  pub deployed_bytecode: String,
+  /// Byte offsets of the library addresses that have to be linked into `bytecode`, by the
+  /// library's qualified name, e.g. `contracts/Math.sol:Math`.
+  #[serde(rename = "linkReferences", default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub link_references: BTreeMap<String, Vec<StandardJsonLinkReference>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ArtifactCompiler {
+  pub name: String,
+  pub version: Option<String>,
+}
+
+impl ContractArtifact {
+  pub fn new(name: &str, contract: &StandardJsonContract, source: &Path, project_path: &Path, compiler: ArtifactCompiler) -> Result<ContractArtifact, CompilerError> {
+    Ok(ContractArtifact {
+      contract_name: name.to_string(),
+      source_path: source_path(source, project_path),
+      source_hash: format!("0x{:x}", Sha3_256::digest(&fs::read(source)?)),
+      compiler,
+      abi: contract.abi.clone(),
+      bytecode: contract.evm.bytecode.object.clone(),
+      deployed_bytecode: contract.evm.deployed_bytecode.object.clone(),
+      link_references: contract.evm.bytecode.link_references.iter()
+        .flat_map(|(library_source, libraries)| {
+          let library_source = source_path(Path::new(library_source), project_path);
+          libraries.iter().map(move |(library, references)| (format!("{}:{}", library_source, library), references.clone()))
+        })
+        .collect(),
+    })
+  }
+
+  /// The path of the JSON artifact with the given name, see `ArtifactNames`.
+  pub fn path(artifacts_dir: &Path, artifact_name: &str) -> PathBuf {
+    artifacts_dir.join(format!("{}.{}", artifact_name, ARTIFACT_EXTENSION_JSON))
+  }
+
+  pub fn read(path: &Path) -> Result<ContractArtifact, CompilerError> {
+    let content = fs::read(path)?;
+    serde_json::from_slice(&content).map_err(|err| CompilerError::InvalidArtifact(path.to_path_buf(), err.to_string()))
+  }
+
+  pub fn write(&self, path: &Path) -> Result<(), CompilerError> {
+    let content = serde_json::to_string_pretty(self).map_err(|err| CompilerError::Other(err.to_string()))?;
+    fs::write(path, content)?;
+    Ok(())
+  }
+}
+
+/// Assigns artifact names to compiled contracts, which are the file names of their artifacts
+/// without extension.
+///
+/// A contract's artifacts are named after the contract, e.g. `Token.abi`, `Token.bin` and
+/// `Token.json`. If contracts of the same name are defined in several source files, each
+/// of them is named `<ContractName>-<SourcePath>` instead, with the source path relative
+/// to the project and `/`, `\` and `.` replaced by `_`, e.g. `Token-contracts_Token_sol`
+/// and `Token-contracts_legacy_Token_sol`. The contract name and source path of an
+/// artifact are part of its JSON artifact.
+#[derive(Debug, Default, PartialEq)]
+pub struct ArtifactNames {
+  /// Artifact names by source path and contract name of the compiled contracts.
+  pub names: BTreeMap<(String, String), String>,
+  /// Existing artifacts that have to be renamed, because a compiled contract has the same
+  /// name now.
+  pub renames: Vec<(String, String)>,
+  /// Existing artifacts of the compiled source files, which are replaced by the compiled contracts.
+  pub outdated: Vec<String>,
+}
+
+impl ArtifactNames {
+  /// Names the contracts compiled from each source file, taking the existing artifacts into
+  /// account. Existing artifacts of source files that don't exist anymore are ignored.
+  pub fn resolve(existing: &[(String, ContractArtifact)], compiled: &BTreeMap<String, BTreeSet<String>>, source_exists: impl Fn(&str) -> bool) -> ArtifactNames {
+    let mut artifact_names = ArtifactNames::default();
+    let mut sources_by_name: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+
+    let mut kept = vec![];
+    for (artifact_name, artifact) in existing {
+      if compiled.contains_key(&artifact.source_path) {
+        artifact_names.outdated.push(artifact_name.clone());
+      } else if source_exists(&artifact.source_path) {
+        sources_by_name.entry(&artifact.contract_name).or_default().insert(&artifact.source_path);
+        kept.push((artifact_name, artifact));
+      }
+    }
+    for (source_path, names) in compiled {
+      for name in names {
+        sources_by_name.entry(name).or_default().insert(source_path);
+      }
+    }
+    let is_ambiguous = |name: &str| sources_by_name.get(name).is_some_and(|sources| sources.len() > 1);
+
+    for (source_path, names) in compiled {
+      for name in names {
+        let artifact_name = if is_ambiguous(name) { qualified_artifact_name(name, source_path) } else { name.clone() };
+        artifact_names.names.insert((source_path.clone(), name.clone()), artifact_name);
+      }
+    }
+    for (artifact_name, artifact) in kept {
+      if *artifact_name == artifact.contract_name && is_ambiguous(&artifact.contract_name) {
+        artifact_names.renames.push((artifact_name.clone(), qualified_artifact_name(&artifact.contract_name, &artifact.source_path)));
+      }
+    }
+    artifact_names
+  }
+}
+
+/// The name of the artifacts of a contract that has the same name as contracts of other
+/// source files, see `ArtifactNames`.
+pub fn qualified_artifact_name(contract_name: &str, source_path: &str) -> String {
+  let source_path = source_path.replace(&['/', '\\', '.'][..], "_");
+  format!("{}{}{}", contract_name, QUALIFIED_NAME_SEPARATOR, source_path)
+}
+
+/// The path of a source file as written to artifacts, relative to the project unless it's
+/// outside of it, with `/` as separator on every platform.
+pub fn source_path(source: &Path, project_path: &Path) -> String {
+  source.strip_prefix(project_path).unwrap_or(source).to_string_lossy().replace('\\', "/")
+}
+
+/// Reads all contract artifacts of the given directory, sorted by contract name. JSON files
+/// that aren't contract artifacts, e.g. metadata written by the compiler, are ignored.
+pub fn read_artifacts(artifacts_dir: &Path) -> Result<Vec<ContractArtifact>, CompilerError> {
+  let mut artifacts = read_named_artifacts(artifacts_dir)?
+    .into_iter()
+    .map(|(_artifact_name, artifact)| artifact)
+    .collect::<Vec<ContractArtifact>>();
+  artifacts.sort_by(|a, b| a.contract_name.cmp(&b.contract_name));
+  Ok(artifacts)
+}
+
+/// Reads all contract artifacts of the given directory together with their artifact names,
+/// sorted by artifact name.
+pub fn read_named_artifacts(artifacts_dir: &Path) -> Result<Vec<(String, ContractArtifact)>, CompilerError> {
+  if !artifacts_dir.exists() {
+    return Ok(vec![]);
+  }
+  let mut artifacts = fs::read_dir(artifacts_dir)?
+    .filter_map(Result::ok)
+    .map(|entry| entry.path())
+    .filter(|path| path.extension().and_then(|extension| extension.to_str()) == Some(ARTIFACT_EXTENSION_JSON))
+    .filter_map(|path| {
+      let artifact_name = path.file_stem()?.to_string_lossy().to_string();
+      ContractArtifact::read(&path).ok().map(|artifact| (artifact_name, artifact))
+    })
+    .collect::<Vec<(String, ContractArtifact)>>();
+  artifacts.sort_by(|a, b| a.0.cmp(&b.0));
+  Ok(artifacts)
+}
+
+#[cfg(test)]
+mod tests {
+
+  extern crate tempfile;
+
+  use std::collections::{BTreeMap, BTreeSet};
+  use std::fs;
+  use tempfile::tempdir;
+  use super::{read_artifacts, ArtifactCompiler, ArtifactNames, ContractArtifact};
+  use crate::compiler::standard_json::{StandardJsonContract, StandardJsonLinkReference};
+
+  #[test]
+  fn it_should_write_and_read_contract_artifacts() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = tempdir()?;
+    let source = tmp_dir.path().join("contracts").join("Token.sol");
+    let artifacts_dir = tmp_dir.path().join("artifacts");
+    fs::create_dir_all(source.parent().unwrap())?;
+    fs::create_dir_all(&artifacts_dir)?;
+    fs::write(&source, "contract Token {}")?;
+
+    let mut contract = StandardJsonContract { abi: serde_json::json!([]), ..StandardJsonContract::default() };
+    contract.evm.bytecode.object = "6080".to_string();
+    contract.evm.deployed_bytecode.object = "60aa".to_string();
+    for (library_source, start) in &[("contracts/Math.sol", 2), ("contracts/legacy/Math.sol", 24)] {
+      let mut libraries = BTreeMap::new();
+      libraries.insert("Math".to_string(), vec![StandardJsonLinkReference { start: *start, length: 20 }]);
+      contract.evm.bytecode.link_references.insert(tmp_dir.path().join(library_source).to_string_lossy().to_string(), libraries);
+    }
+    let compiler = ArtifactCompiler { name: "solc".to_string(), version: Some("0.5.8".to_string()) };
+
+    let artifact = ContractArtifact::new("Token", &contract, &source, tmp_dir.path(), compiler)?;
+    assert_eq!(artifact.source_path, "contracts/Token.sol");
+    assert!(artifact.source_hash.starts_with("0x"));
+    assert_eq!(artifact.link_references.keys().collect::<Vec<&String>>(), vec!["contracts/Math.sol:Math", "contracts/legacy/Math.sol:Math"]);
+    artifact.write(&ContractArtifact::path(&artifacts_dir, "Token"))?;
+    fs::write(artifacts_dir.join("Token_meta.json"), "{}")?;
+
+    assert_eq!(ContractArtifact::read(&ContractArtifact::path(&artifacts_dir, "Token"))?, artifact);
+    assert_eq!(read_artifacts(&artifacts_dir)?, vec![artifact]);
+    assert!(ContractArtifact::read(&artifacts_dir.join("Token_meta.json")).is_err());
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  fn artifact(name: &str, source_path: &str) -> ContractArtifact {
+    ContractArtifact {
+      contract_name: name.to_string(),
+      source_path: source_path.to_string(),
+      source_hash: "0x".to_string(),
+      compiler: ArtifactCompiler { name: "solc".to_string(), version: None },
+      abi: serde_json::json!([]),
+      bytecode: String::new(),
+      deployed_bytecode: String::new(),
+      link_references: BTreeMap::new(),
+    }
+  }
+
+  fn compiled(contracts: &[(&str, &[&str])]) -> BTreeMap<String, BTreeSet<String>> {
+    contracts.iter()
+      .map(|(source_path, names)| (source_path.to_string(), names.iter().map(|name| name.to_string()).collect()))
+      .collect()
+  }
+
+  #[test]
+  fn it_should_qualify_artifact_names_of_contracts_defined_in_several_sources() {
+    let existing = vec![
+      ("Token".to_string(), artifact("Token", "contracts/Token.sol")),
+      ("Ownable".to_string(), artifact("Ownable", "contracts/Ownable.sol")),
+      ("Sale".to_string(), artifact("Sale", "contracts/Removed.sol")),
+    ];
+    let compiled = compiled(&[
+      ("contracts/legacy/Token.sol", &["Token", "Sale"]),
+      ("contracts/Ownable.sol", &["Ownable"]),
+    ]);
+    let names = ArtifactNames::resolve(&existing, &compiled, |source_path| source_path != "contracts/Removed.sol");
+
+    let name = |source_path: &str, name: &str| names.names[&(source_path.to_string(), name.to_string())].clone();
+    assert_eq!(name("contracts/legacy/Token.sol", "Token"), "Token-contracts_legacy_Token_sol");
+    assert_eq!(name("contracts/legacy/Token.sol", "Sale"), "Sale");
+    assert_eq!(name("contracts/Ownable.sol", "Ownable"), "Ownable");
+    assert_eq!(names.renames, vec![("Token".to_string(), "Token-contracts_Token_sol".to_string())]);
+    assert_eq!(names.outdated, vec!["Ownable".to_string()]);
+  }
+}
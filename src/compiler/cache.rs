@@ -0,0 +1,277 @@
+use super::error::CompilerError;
+use super::imports;
+use super::remappings::Remapping;
+use super::utils;
+
+use sha3::{Digest, Sha3_256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml;
+
+pub const COMPILE_CACHE_FILE: &str = "compile_cache.toml";
+
+/// Identifies the compiler and options used for a compilation. Compilation units
+/// have to be recompiled whenever it changes.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct CompilerFingerprint {
+  pub compiler: String,
+  pub version: Option<String>,
+  pub options: Vec<String>,
+  pub artifacts_dir: String,
+  pub smart_imports: bool,
+  pub remappings: Vec<String>,
+  /// Maximum runtime and initcode size and whether exceeding them is an error, because
+  /// Smart Contracts are only checked against them when they're compiled.
+  pub size_limits: (usize, usize, bool),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CachedUnit {
+  pub hash: String,
+  /// Names of the Smart Contracts compiled from the unit's source file.
+  pub artifacts: Vec<String>,
+  /// The compiler the unit has been compiled with. Units can be compiled by different
+  /// compilers, e.g. in projects with Solidity and Vyper source files.
+  pub fingerprint: CompilerFingerprint,
+}
+
+/// Content hashes of all compilation units at the time they were compiled last.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct CompileCache {
+  pub units: BTreeMap<String, CachedUnit>,
+}
+
+/// A source file together with all files it imports, directly or transitively.
+#[derive(Debug)]
+pub struct CompilationUnit {
+  pub source: PathBuf,
+  /// Hash of the contents of all files of the unit. Units with imports that can't be
+  /// resolved have no hash and are always compiled.
+  pub hash: Option<String>,
+  /// The `pragma solidity` version requirements of all files of the unit.
+  pub version_pragmas: BTreeSet<String>,
+  /// The source file and all files it imports that could be resolved.
+  pub files: BTreeSet<PathBuf>,
+}
+
+impl CompilationUnit {
+  pub fn new(source: PathBuf, project_path: &Path, remappings: &[Remapping]) -> Result<CompilationUnit, CompilerError> {
+    let mut files = BTreeSet::new();
+    let mut unread = vec![source.clone()];
+    let mut version_pragmas = BTreeSet::new();
+    let mut resolved = true;
+
+    while let Some(path) = unread.pop() {
+      if files.contains(&path) {
+        continue;
+      }
+      let contents = fs::read_to_string(&path)?;
+      version_pragmas.extend(imports::parse_version_pragmas(&contents));
+      for import in imports::parse_imports(&contents) {
+        match utils::resolve_import_path(&import.path, path.parent().unwrap(), project_path, remappings) {
+          Ok((_import_type, import_path)) => unread.push(import_path),
+          Err(_err) => resolved = false,
+        }
+      }
+      files.insert(path);
+    }
+
+    let hash = if resolved { Some(hash_files(&files)?) } else { None };
+    Ok(CompilationUnit { source, hash, version_pragmas, files })
+  }
+}
+
+impl CompileCache {
+  /// Reads the cache from the given file. A missing or unreadable cache is treated
+  /// as empty, which causes all compilation units to be compiled.
+  pub fn read(cache_file: &Path) -> CompileCache {
+    fs::read_to_string(cache_file)
+      .ok()
+      .and_then(|contents| toml::from_str(&contents).ok())
+      .unwrap_or_default()
+  }
+
+  pub fn write(&self, cache_file: &Path) -> Result<(), CompilerError> {
+    let contents = toml::to_string(self).map_err(|err| CompilerError::Other(format!("Couldn't write compile cache: {}", err)))?;
+    fs::write(cache_file, contents)?;
+    Ok(())
+  }
+
+  /// Whether the unit has been compiled with the given compiler before and neither
+  /// its files nor its artifacts have changed since.
+  pub fn is_up_to_date(&self, unit: &CompilationUnit, fingerprint: &CompilerFingerprint, artifact_exists: impl Fn(&str) -> bool) -> bool {
+    match (&unit.hash, self.units.get(&unit.source.to_string_lossy().to_string())) {
+      (Some(hash), Some(cached)) => {
+        cached.fingerprint == *fingerprint && *hash == cached.hash && cached.artifacts.iter().all(|name| artifact_exists(name))
+      },
+      _ => false,
+    }
+  }
+
+  /// Records that the unit has been compiled with `fingerprint` into the given artifacts.
+  pub fn update(&mut self, unit: &CompilationUnit, fingerprint: &CompilerFingerprint, artifacts: Vec<String>) {
+    let key = unit.source.to_string_lossy().to_string();
+    match &unit.hash {
+      Some(hash) => {
+        self.units.insert(key, CachedUnit { hash: hash.clone(), artifacts, fingerprint: fingerprint.clone() });
+      },
+      None => {
+        self.units.remove(&key);
+      },
+    }
+  }
+
+  /// Renames an artifact of the cached units, see `artifact::ArtifactNames`.
+  pub fn rename_artifact(&mut self, name: &str, new_name: &str) {
+    for cached_unit in self.units.values_mut() {
+      for artifact in cached_unit.artifacts.iter_mut().filter(|artifact| *artifact == name) {
+        *artifact = new_name.to_string();
+      }
+    }
+  }
+
+  /// Removes units of source files that aren't compiled anymore.
+  pub fn retain(&mut self, units: &[CompilationUnit]) {
+    let sources = units.iter().map(|unit| unit.source.to_string_lossy().to_string()).collect::<BTreeSet<String>>();
+    self.units.retain(|source, _cached_unit| sources.contains(source));
+  }
+}
+
+fn hash_files(files: &BTreeSet<PathBuf>) -> Result<String, CompilerError> {
+  let mut hasher = Sha3_256::new();
+  for file in files {
+    hasher.input(file.to_string_lossy().as_bytes());
+    hasher.input(fs::read(file)?);
+  }
+  Ok(format!("0x{:x}", hasher.result()))
+}
+
+#[cfg(test)]
+mod tests {
+
+  extern crate tempfile;
+
+  use std::fs;
+  use std::path::PathBuf;
+  use tempfile::{tempdir, TempDir};
+  use super::{CompilationUnit, CompileCache, CompilerFingerprint, Remapping};
+  use crate::utils::adjust_canonicalization;
+
+  fn create_project() -> Result<(TempDir, PathBuf), Box<dyn std::error::Error>> {
+    let tmp_dir = tempdir()?;
+    let project_path = adjust_canonicalization(tmp_dir.path().canonicalize()?);
+    fs::create_dir_all(project_path.join("contracts"))?;
+    fs::create_dir_all(project_path.join("node_modules").join("lib"))?;
+    fs::write(project_path.join("contracts").join("Token.sol"), "import \"./Ownable.sol\";\nimport \"lib/Math.sol\";\ncontract Token {}")?;
+    fs::write(project_path.join("contracts").join("Ownable.sol"), "contract Ownable {}")?;
+    fs::write(project_path.join("node_modules").join("lib").join("Math.sol"), "pragma solidity ^0.5.0;\nlibrary Math {}")?;
+    Ok((tmp_dir, project_path))
+  }
+
+  fn fingerprint() -> CompilerFingerprint {
+    CompilerFingerprint {
+      compiler: "solc".to_string(),
+      version: Some("0.5.8".to_string()),
+      options: vec!["--standard-json".to_string()],
+      ..CompilerFingerprint::default()
+    }
+  }
+
+  fn cache_with(unit: &CompilationUnit, artifacts: Vec<String>) -> CompileCache {
+    let mut cache = CompileCache::default();
+    cache.update(unit, &fingerprint(), artifacts);
+    cache
+  }
+
+  #[test]
+  fn it_should_include_node_module_imports_in_compilation_unit() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, project_path) = create_project()?;
+    let source = project_path.join("contracts").join("Token.sol");
+
+    let unit = CompilationUnit::new(source.clone(), &project_path, &[])?;
+    fs::write(project_path.join("node_modules").join("lib").join("Math.sol"), "library Math { }")?;
+    let changed_unit = CompilationUnit::new(source, &project_path, &[])?;
+
+    assert!(unit.hash.is_some());
+    assert_ne!(unit.hash, changed_unit.hash);
+    assert!(unit.version_pragmas.contains("^0.5.0"));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_ignore_commented_out_version_pragmas() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, project_path) = create_project()?;
+    let source = project_path.join("contracts").join("Legacy.sol");
+    fs::write(&source, "// pragma solidity ^0.4.0;\npragma solidity ^0.5.0;\ncontract Legacy { }")?;
+
+    let unit = CompilationUnit::new(source, &project_path, &[])?;
+    assert_eq!(unit.version_pragmas.into_iter().collect::<Vec<String>>(), vec!["^0.5.0"]);
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_detect_changes_of_imported_files() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, project_path) = create_project()?;
+    let source = project_path.join("contracts").join("Token.sol");
+
+    let unit = CompilationUnit::new(source.clone(), &project_path, &[])?;
+    let cache = cache_with(&unit, vec![]);
+    assert!(cache.is_up_to_date(&unit, &fingerprint(), |_name| true));
+
+    fs::write(project_path.join("contracts").join("Ownable.sol"), "contract Ownable { address owner; }")?;
+    let changed_unit = CompilationUnit::new(source, &project_path, &[])?;
+    assert!(!cache.is_up_to_date(&changed_unit, &fingerprint(), |_name| true));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_not_be_up_to_date_if_compiler_or_artifacts_changed() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, project_path) = create_project()?;
+
+    let unit = CompilationUnit::new(project_path.join("contracts").join("Token.sol"), &project_path, &[])?;
+    let cache = cache_with(&unit, vec!["Token".to_string()]);
+
+    let other_compiler = CompilerFingerprint { version: Some("0.5.9".to_string()), ..fingerprint() };
+    assert!(!cache.is_up_to_date(&unit, &other_compiler, |_name| true));
+    assert!(!cache.is_up_to_date(&unit, &fingerprint(), |_name| false));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_resolve_remapped_imports() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, project_path) = create_project()?;
+    let source = project_path.join("contracts").join("Remapped.sol");
+    fs::write(&source, "import \"@lib/Math.sol\";")?;
+    let remappings = vec![Remapping::parse("@lib/=node_modules/lib/")?.resolve(&project_path)];
+
+    let unit = CompilationUnit::new(source.clone(), &project_path, &remappings)?;
+    assert!(unit.version_pragmas.contains("^0.5.0"));
+    assert!(CompilationUnit::new(source, &project_path, &[])?.hash.is_none());
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_always_compile_units_with_unresolved_imports() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, project_path) = create_project()?;
+    let source = project_path.join("contracts").join("Remapped.sol");
+    fs::write(&source, "import \"remapped/Missing.sol\";")?;
+
+    let unit = CompilationUnit::new(source, &project_path, &[])?;
+    assert!(unit.hash.is_none());
+    assert!(!CompileCache::default().is_up_to_date(&unit, &CompilerFingerprint::default(), |_name| true));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+}
@@ -0,0 +1,230 @@
+use super::standard_json::{StandardJsonError, StandardJsonInput};
+
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Maps source files that have been normalized by `Compiler::normalize_imports` to the
+/// original source files they have been created from.
+pub type SourceMapping = BTreeMap<PathBuf, PathBuf>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticSeverity {
+  Error,
+  Warning,
+  Info,
+}
+
+impl DiagnosticSeverity {
+  fn from_str(severity: &str) -> DiagnosticSeverity {
+    match severity.to_lowercase().as_str() {
+      "warning" => DiagnosticSeverity::Warning,
+      "info" => DiagnosticSeverity::Info,
+      _ => DiagnosticSeverity::Error,
+    }
+  }
+}
+
+impl fmt::Display for DiagnosticSeverity {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      DiagnosticSeverity::Error => write!(f, "error"),
+      DiagnosticSeverity::Warning => write!(f, "warning"),
+      DiagnosticSeverity::Info => write!(f, "info"),
+    }
+  }
+}
+
+/// A warning or error reported by a compiler. Lines and columns start at 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompilerDiagnostic {
+  pub severity: DiagnosticSeverity,
+  pub file: Option<PathBuf>,
+  pub line: Option<usize>,
+  pub column: Option<usize>,
+  pub message: String,
+  pub code: Option<String>,
+}
+
+impl CompilerDiagnostic {
+  pub fn is_error(&self) -> bool {
+    self.severity == DiagnosticSeverity::Error
+  }
+
+  /// Creates a diagnostic from an error of the standard JSON interface. Source locations
+  /// are byte offsets, which are translated into lines and columns using the given input.
+  pub fn from_standard_json(error: &StandardJsonError, input: &StandardJsonInput) -> CompilerDiagnostic {
+    let location = error.source_location.as_ref().map(|location| {
+      let position = input.sources.get(&location.file)
+        .filter(|_source| location.start >= 0)
+        .map(|source| line_and_column(&source.content, location.start as usize));
+      (PathBuf::from(&location.file), position)
+    });
+
+    CompilerDiagnostic {
+      severity: DiagnosticSeverity::from_str(&error.severity),
+      file: location.as_ref().map(|(file, _position)| file.clone()),
+      line: location.as_ref().and_then(|(_file, position)| position.map(|(line, _column)| line)),
+      column: location.as_ref().and_then(|(_file, position)| position.map(|(_line, column)| column)),
+      message: error.message.clone(),
+      code: error.error_code.clone(),
+    }
+  }
+
+  /// Translates the diagnostic's file from a normalized source file back to its original
+  /// source file. Files inside the project are made relative to `project_path`.
+  pub fn map_to_original_source(&mut self, mapping: &SourceMapping, project_path: &Path) {
+    if let Some(file) = self.file.take() {
+      let original = mapping.get(&file).cloned().unwrap_or(file);
+      self.file = Some(original.strip_prefix(project_path).map(Path::to_path_buf).unwrap_or(original));
+    }
+  }
+}
+
+impl fmt::Display for CompilerDiagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match &self.code {
+      Some(code) => write!(f, "{}[{}]: {}", self.severity, code, self.message)?,
+      None => write!(f, "{}: {}", self.severity, self.message)?,
+    }
+    if let Some(file) = &self.file {
+      write!(f, "\n  --> {}", file.display())?;
+      if let Some(line) = self.line {
+        write!(f, ":{}", line)?;
+      }
+      if let Some(column) = self.column {
+        write!(f, ":{}", column)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Parses the human readable output of solc and solcjs. Both the `file:line:column: Kind: message`
+/// format of older compiler versions and the `Kind: message` followed by ` --> file:line:column:`
+/// format of newer versions are supported. Lines that don't belong to a diagnostic are ignored.
+pub fn parse(output: &str) -> Vec<CompilerDiagnostic> {
+  let located_header = Regex::new(r"^(?P<file>.+?):(?P<line>\d+):(?P<column>\d+):\s*(?P<kind>\w*Error|Warning|Info)(?: \((?P<code>\d+)\))?: (?P<message>.*)$").unwrap();
+  let header = Regex::new(r"^(?P<kind>\w*Error|Warning|Info)(?: \((?P<code>\d+)\))?: (?P<message>.*)$").unwrap();
+  let location = Regex::new(r"^\s*--> (?P<file>.+?):(?P<line>\d+):(?P<column>\d+):?\s*$").unwrap();
+
+  let mut diagnostics: Vec<CompilerDiagnostic> = vec![];
+  let mut expects_location = false;
+
+  for line in output.lines() {
+    if let Some(captures) = located_header.captures(line) {
+      diagnostics.push(CompilerDiagnostic {
+        severity: severity_of_kind(&captures["kind"]),
+        file: Some(PathBuf::from(&captures["file"])),
+        line: captures["line"].parse().ok(),
+        column: captures["column"].parse().ok(),
+        message: captures["message"].to_string(),
+        code: captures.name("code").map(|code| code.as_str().to_string()),
+      });
+      expects_location = false;
+    } else if let Some(captures) = header.captures(line) {
+      diagnostics.push(CompilerDiagnostic {
+        severity: severity_of_kind(&captures["kind"]),
+        file: None,
+        line: None,
+        column: None,
+        message: captures["message"].to_string(),
+        code: captures.name("code").map(|code| code.as_str().to_string()),
+      });
+      expects_location = true;
+    } else if let Some(captures) = location.captures(line).filter(|_captures| expects_location) {
+      if let Some(diagnostic) = diagnostics.last_mut() {
+        diagnostic.file = Some(PathBuf::from(&captures["file"]));
+        diagnostic.line = captures["line"].parse().ok();
+        diagnostic.column = captures["column"].parse().ok();
+      }
+      expects_location = false;
+    }
+  }
+  diagnostics
+}
+
+// Solc reports the kind of a diagnostic, such as `ParserError` or `Warning`, instead of its severity.
+fn severity_of_kind(kind: &str) -> DiagnosticSeverity {
+  match kind {
+    "Warning" => DiagnosticSeverity::Warning,
+    "Info" => DiagnosticSeverity::Info,
+    _ => DiagnosticSeverity::Error,
+  }
+}
+
+fn line_and_column(content: &str, offset: usize) -> (usize, usize) {
+  let preceding = &content.as_bytes()[..offset.min(content.len())];
+  let line = preceding.iter().filter(|byte| **byte == b'\n').count() + 1;
+  let line_start = preceding.iter().rposition(|byte| *byte == b'\n').map(|position| position + 1).unwrap_or(0);
+  (line, String::from_utf8_lossy(&preceding[line_start..]).chars().count() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+
+  use std::collections::BTreeMap;
+  use std::path::{Path, PathBuf};
+  use super::{parse, CompilerDiagnostic, DiagnosticSeverity, SourceMapping};
+  use crate::compiler::standard_json::{StandardJsonInput, StandardJsonOutput, StandardJsonSettings, StandardJsonSource};
+
+  #[test]
+  fn it_should_parse_diagnostics_of_older_compiler_versions() {
+    let output = "/project/.vibranium/contracts/Token.sol:5:9: Warning: Unused local variable.\n        uint x;\n        ^----^\n/project/.vibranium/contracts/Token.sol:7:5: DeclarationError: Undeclared identifier.\n";
+
+    assert_eq!(parse(output), vec![
+      CompilerDiagnostic {
+        severity: DiagnosticSeverity::Warning,
+        file: Some(PathBuf::from("/project/.vibranium/contracts/Token.sol")),
+        line: Some(5),
+        column: Some(9),
+        message: "Unused local variable.".to_string(),
+        code: None,
+      },
+      CompilerDiagnostic {
+        severity: DiagnosticSeverity::Error,
+        file: Some(PathBuf::from("/project/.vibranium/contracts/Token.sol")),
+        line: Some(7),
+        column: Some(5),
+        message: "Undeclared identifier.".to_string(),
+        code: None,
+      },
+    ]);
+  }
+
+  #[test]
+  fn it_should_parse_diagnostics_of_newer_compiler_versions() {
+    let output = "Error (7576): Undeclared identifier.\n --> contracts/Token.sol:7:5:\n  |\n7 |     y = 1;\n  |     ^\n\nError: No input files given.\n";
+    let diagnostics = parse(output);
+
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].code, Some("7576".to_string()));
+    assert_eq!(diagnostics[0].file, Some(PathBuf::from("contracts/Token.sol")));
+    assert_eq!((diagnostics[0].line, diagnostics[0].column), (Some(7), Some(5)));
+    assert_eq!(diagnostics[1].file, None);
+    assert!(diagnostics[1].is_error());
+  }
+
+  #[test]
+  fn it_should_translate_source_locations_of_standard_json_errors() {
+    let file = "/project/.vibranium/contracts/Token.sol";
+    let mut sources = BTreeMap::new();
+    sources.insert(file.to_string(), StandardJsonSource { content: "contract Token {\n  function f() public { uint x; }\n}".to_string() });
+    let input = StandardJsonInput {
+      language: "Solidity".to_string(),
+      sources,
+      settings: StandardJsonSettings { remappings: vec![], output_selection: BTreeMap::new() },
+    };
+    let output = StandardJsonOutput::parse(br#"{ "errors": [{ "severity": "warning", "type": "Warning", "message": "Unused local variable.", "errorCode": "2072",
+      "sourceLocation": { "file": "/project/.vibranium/contracts/Token.sol", "start": 41, "end": 47 } }] }"#).unwrap();
+
+    let mut diagnostic = CompilerDiagnostic::from_standard_json(&output.errors[0], &input);
+    let mut mapping = SourceMapping::new();
+    mapping.insert(PathBuf::from(file), PathBuf::from("/project/contracts/Token.sol"));
+    diagnostic.map_to_original_source(&mapping, Path::new("/project"));
+
+    assert_eq!((diagnostic.line, diagnostic.column), (Some(2), Some(25)));
+    assert_eq!(diagnostic.to_string(), "warning[2072]: Unused local variable.\n  --> contracts/Token.sol:2:25");
+  }
+}
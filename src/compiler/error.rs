@@ -5,6 +5,7 @@ use std::io;
 use std::path::PathBuf;
 
 use crate::config;
+use super::diagnostics::CompilerDiagnostic;
 use crate::project_generator;
 
 #[derive(Debug)]
@@ -15,6 +16,11 @@ pub enum CompilerError {
   InvalidConfig(config::error::ConfigError),
   ImportError(PathBuf),
   UnsupportedStrategy,
+  InvalidVersionPragma(String, String),
+  InvalidRemapping(String),
+  NoMatchingSolcVersion(PathBuf, Vec<String>),
+  InvalidArtifact(PathBuf, String),
+  CompilationFailed(Vec<CompilerDiagnostic>),
   Other(String),
 }
 
@@ -26,6 +32,11 @@ impl Error for CompilerError {
       CompilerError::VibraniumDirectoryNotFound(error) => Some(error),
       CompilerError::InvalidConfig(error) => Some(error),
       CompilerError::UnsupportedStrategy => None,
+      CompilerError::InvalidVersionPragma(_pragma, _message) => None,
+      CompilerError::InvalidRemapping(_remapping) => None,
+      CompilerError::NoMatchingSolcVersion(_source, _pragmas) => None,
+      CompilerError::InvalidArtifact(_path, _message) => None,
+      CompilerError::CompilationFailed(_diagnostics) => None,
       CompilerError::ImportError(_path) => None,
       CompilerError::Other(_message) => None,
     }
@@ -40,7 +51,20 @@ impl fmt::Display for CompilerError {
       CompilerError::VibraniumDirectoryNotFound(error) => write!(f, "{}", error),
       CompilerError::InvalidConfig(error) => write!(f, "{}", error),
       CompilerError::UnsupportedStrategy => write!(f, "Couldn't compile project without `CompilerConfig::compiler_options`. No built-in support for requested compiler."),
+      CompilerError::InvalidVersionPragma(pragma, message) => write!(f, "Couldn't compile project. Invalid solc version requirement {:?}: {}", pragma, message),
+      CompilerError::InvalidRemapping(remapping) => write!(f, "Couldn't compile project. Invalid remapping {:?}, expected `[context:]prefix=target`", remapping),
+      CompilerError::NoMatchingSolcVersion(source, pragmas) => {
+        write!(f, "Couldn't compile {:?}. None of the solc versions installed in ~/.vibranium/compilers satisfies {}", source, pragmas.join(", "))
+      },
+      CompilerError::InvalidArtifact(path, message) => write!(f, "Couldn't read artifact {:?}: {}", path, message),
       CompilerError::ImportError(path) => write!(f, "Couldn't compile project. Import file doesn't exist: {:?}", path),
+      CompilerError::CompilationFailed(diagnostics) => {
+        for diagnostic in diagnostics {
+          write!(f, "{}\n\n", diagnostic)?;
+        }
+        let errors = diagnostics.iter().filter(|diagnostic| diagnostic.is_error()).count();
+        write!(f, "Couldn't compile project due to {} previous error(s)", errors)
+      },
       CompilerError::Other(message) => write!(f, "{}", &message),
     }
   }
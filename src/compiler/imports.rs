@@ -0,0 +1,359 @@
+use std::ops::Range;
+
+/// An import directive of a Solidity source file, in any of its forms:
+///
+/// - `import "path";` and `import "path" as Alias;`
+/// - `import * as Alias from "path";`
+/// - `import {A, B as C} from "path";`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportDirective {
+  pub path: String,
+  /// Byte range of the path within the source file, excluding quotes.
+  pub path_range: Range<usize>,
+  /// The alias of the imported source unit, `Alias` in `import "path" as Alias;`
+  /// and `import * as Alias from "path";`.
+  pub unit_alias: Option<String>,
+  /// Imported symbols with their aliases, in `import {A, B as C} from "path";`.
+  pub symbols: Vec<(String, Option<String>)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+  Identifier(String),
+  StringLiteral(String, Range<usize>),
+  Symbol(char),
+}
+
+/// Returns the import directives of a Solidity source file. Imports within comments and
+/// string literals are ignored, just like malformed import directives.
+pub fn parse_imports(source: &str) -> Vec<ImportDirective> {
+  let tokens = tokenize(source).into_iter().map(|(_start, token)| token).collect::<Vec<Token>>();
+  let mut imports = vec![];
+  let mut position = 0;
+
+  while position < tokens.len() {
+    if tokens[position] == Token::Identifier("import".to_string()) {
+      let mut parser = ImportParser { tokens: &tokens, position: position + 1 };
+      if let Some(import) = parser.parse() {
+        imports.push(import);
+      }
+      position = parser.position;
+    } else {
+      position += 1;
+    }
+  }
+  imports
+}
+
+/// Returns the version requirements of `pragma solidity ...;` directives, e.g. `^0.5.0`.
+/// Like imports, pragmas within comments and string literals are ignored.
+pub fn parse_version_pragmas(source: &str) -> Vec<String> {
+  let tokens = tokenize(source);
+  let mut pragmas = vec![];
+
+  for (index, window) in tokens.windows(3).enumerate() {
+    if let [(_, Token::Identifier(pragma)), (_, Token::Identifier(name)), (start, _)] = window {
+      if pragma != "pragma" || name != "solidity" {
+        continue;
+      }
+      let end = tokens[index + 2..].iter().find(|(_start, token)| *token == Token::Symbol(';'));
+      if let Some((end, _token)) = end {
+        let requirement = source[*start..*end].trim();
+        if !requirement.is_empty() {
+          pragmas.push(requirement.to_string());
+        }
+      }
+    }
+  }
+  pragmas
+}
+
+/// Replaces the paths of the given imports with new paths. Everything else, like symbol
+/// aliases, comments and formatting, is preserved.
+pub fn rewrite_import_paths(source: &str, rewrites: &[(&ImportDirective, String)]) -> String {
+  let mut rewrites = rewrites.to_vec();
+  rewrites.sort_by_key(|(import, _path)| import.path_range.start);
+
+  let mut rewritten = String::with_capacity(source.len());
+  let mut end = 0;
+  for (import, path) in rewrites {
+    rewritten.push_str(&source[end..import.path_range.start]);
+    rewritten.push_str(&path);
+    end = import.path_range.end;
+  }
+  rewritten.push_str(&source[end..]);
+  rewritten
+}
+
+struct ImportParser<'a> {
+  tokens: &'a [Token],
+  position: usize,
+}
+
+impl<'a> ImportParser<'a> {
+  fn parse(&mut self) -> Option<ImportDirective> {
+    let import = match self.next()? {
+      Token::StringLiteral(path, path_range) => {
+        let unit_alias = if self.next_if_identifier("as") {
+          Some(self.identifier()?)
+        } else {
+          None
+        };
+        ImportDirective { path, path_range, unit_alias, symbols: vec![] }
+      },
+      Token::Symbol('*') => {
+        self.expect_identifier("as")?;
+        let unit_alias = Some(self.identifier()?);
+        self.expect_identifier("from")?;
+        let (path, path_range) = self.string_literal()?;
+        ImportDirective { path, path_range, unit_alias, symbols: vec![] }
+      },
+      Token::Symbol('{') => {
+        let symbols = self.symbol_aliases()?;
+        self.expect_identifier("from")?;
+        let (path, path_range) = self.string_literal()?;
+        ImportDirective { path, path_range, unit_alias: None, symbols }
+      },
+      _ => return None,
+    };
+    match self.next()? {
+      Token::Symbol(';') => Some(import),
+      _ => None,
+    }
+  }
+
+  // Parses `A, B as C}`, the opening brace has been consumed already.
+  fn symbol_aliases(&mut self) -> Option<Vec<(String, Option<String>)>> {
+    let mut symbols = vec![];
+    loop {
+      match self.next()? {
+        Token::Symbol('}') if symbols.is_empty() => return Some(symbols),
+        Token::Identifier(symbol) => {
+          let alias = if self.next_if_identifier("as") {
+            Some(self.identifier()?)
+          } else {
+            None
+          };
+          symbols.push((symbol, alias));
+        },
+        _ => return None,
+      }
+      match self.next()? {
+        Token::Symbol(',') => continue,
+        Token::Symbol('}') => return Some(symbols),
+        _ => return None,
+      }
+    }
+  }
+
+  fn next(&mut self) -> Option<Token> {
+    let token = self.tokens.get(self.position).cloned();
+    self.position += 1;
+    token
+  }
+
+  fn identifier(&mut self) -> Option<String> {
+    match self.next()? {
+      Token::Identifier(identifier) => Some(identifier),
+      _ => None,
+    }
+  }
+
+  fn string_literal(&mut self) -> Option<(String, Range<usize>)> {
+    match self.next()? {
+      Token::StringLiteral(value, range) => Some((value, range)),
+      _ => None,
+    }
+  }
+
+  fn expect_identifier(&mut self, expected: &str) -> Option<()> {
+    match self.next()? {
+      Token::Identifier(ref identifier) if identifier == expected => Some(()),
+      _ => None,
+    }
+  }
+
+  fn next_if_identifier(&mut self, expected: &str) -> bool {
+    match self.tokens.get(self.position) {
+      Some(Token::Identifier(identifier)) if identifier == expected => {
+        self.position += 1;
+        true
+      },
+      _ => false,
+    }
+  }
+}
+
+// Splits the source into identifiers, string literals and other symbols along with their
+// start offsets, skipping whitespace and comments. Numbers and operators end up as symbols
+// and identifiers, which is good enough to find import and pragma directives.
+fn tokenize(source: &str) -> Vec<(usize, Token)> {
+  let mut tokens = vec![];
+  let mut chars = source.char_indices().peekable();
+
+  while let Some((start, c)) = chars.next() {
+    match c {
+      '/' if chars.peek().map(|(_, next)| *next) == Some('/') => {
+        for (_, c) in chars.by_ref() {
+          if c == '\n' {
+            break;
+          }
+        }
+      },
+      '/' if chars.peek().map(|(_, next)| *next) == Some('*') => {
+        chars.next();
+        let mut previous = ' ';
+        for (_, c) in chars.by_ref() {
+          if previous == '*' && c == '/' {
+            break;
+          }
+          previous = c;
+        }
+      },
+      '"' | '\'' => {
+        let quote = c;
+        let mut value = String::new();
+        let mut end = source.len();
+        while let Some((index, c)) = chars.next() {
+          match c {
+            '\\' => {
+              if let Some((_, escaped)) = chars.next() {
+                value.push(escaped);
+              }
+            },
+            // Unterminated string literals end at the line break.
+            '\n' => {
+              end = index;
+              break;
+            },
+            _ if c == quote => {
+              end = index;
+              break;
+            },
+            _ => value.push(c),
+          }
+        }
+        tokens.push((start, Token::StringLiteral(value, start + 1..end)));
+      },
+      _ if c.is_alphabetic() || c == '_' || c == '$' => {
+        let mut identifier = c.to_string();
+        while let Some((_, c)) = chars.peek() {
+          if c.is_alphanumeric() || *c == '_' || *c == '$' {
+            identifier.push(*c);
+            chars.next();
+          } else {
+            break;
+          }
+        }
+        tokens.push((start, Token::Identifier(identifier)));
+      },
+      _ if c.is_whitespace() => (),
+      _ => tokens.push((start, Token::Symbol(c))),
+    }
+  }
+  tokens
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::{parse_imports, parse_version_pragmas, rewrite_import_paths, ImportDirective};
+
+  fn paths(source: &str) -> Vec<String> {
+    parse_imports(source).into_iter().map(|import| import.path).collect()
+  }
+
+  #[test]
+  fn it_should_parse_plain_imports() {
+    let imports = parse_imports("import \"./Ownable.sol\";\nimport './Math.sol';");
+
+    assert_eq!(imports.len(), 2);
+    assert_eq!(imports[0], ImportDirective {
+      path: "./Ownable.sol".to_string(),
+      path_range: 8..21,
+      unit_alias: None,
+      symbols: vec![],
+    });
+    assert_eq!(imports[1].path, "./Math.sol");
+  }
+
+  #[test]
+  fn it_should_parse_imports_with_unit_alias() {
+    let imports = parse_imports("import \"lib/Math.sol\" as Math;");
+
+    assert_eq!(imports[0].path, "lib/Math.sol");
+    assert_eq!(imports[0].unit_alias, Some("Math".to_string()));
+  }
+
+  #[test]
+  fn it_should_parse_wildcard_imports() {
+    let imports = parse_imports("import * as Tokens from \"./tokens/Tokens.sol\";");
+
+    assert_eq!(imports[0].path, "./tokens/Tokens.sol");
+    assert_eq!(imports[0].unit_alias, Some("Tokens".to_string()));
+  }
+
+  #[test]
+  fn it_should_parse_imports_with_symbol_aliases() {
+    let imports = parse_imports("import {\n  ERC20,\n  SafeMath as Math\n} from '@oz/token/ERC20.sol';");
+
+    assert_eq!(imports[0].path, "@oz/token/ERC20.sol");
+    assert_eq!(imports[0].symbols, vec![
+      ("ERC20".to_string(), None),
+      ("SafeMath".to_string(), Some("Math".to_string())),
+    ]);
+    assert_eq!(paths("import {} from \"./Empty.sol\";"), vec!["./Empty.sol"]);
+  }
+
+  #[test]
+  fn it_should_ignore_imports_in_comments_and_string_literals() {
+    let source = "
+      // import \"./Commented.sol\";
+      /* import \"./Block.sol\";
+         import \"./Block2.sol\"; */
+      import \"./Real.sol\"; /** trailing */
+      contract Token {
+        string constant IMPORT = \"import \\\"./String.sol\\\";\";
+      }
+    ";
+
+    assert_eq!(paths(source), vec!["./Real.sol"]);
+  }
+
+  #[test]
+  fn it_should_ignore_malformed_imports() {
+    assert!(paths("import \"./Missing.sol\"").is_empty());
+    assert!(paths("import * from \"./Wildcard.sol\";").is_empty());
+    assert_eq!(paths("import {A as} from \"./A.sol\"; import \"./B.sol\";"), vec!["./B.sol"]);
+  }
+
+  #[test]
+  fn it_should_parse_version_pragmas_outside_of_comments_and_string_literals() {
+    let source = "
+      // pragma solidity ^0.4.0;
+      /* pragma solidity 0.4.24; */
+      pragma solidity >=0.5.0 <0.6.0;
+      pragma experimental ABIEncoderV2;
+      contract Token {
+        string constant PRAGMA = \"pragma solidity ^0.3.0;\";
+      }
+    ";
+
+    assert_eq!(parse_version_pragmas(source), vec![">=0.5.0 <0.6.0"]);
+    assert_eq!(parse_version_pragmas("pragma solidity ^0.5.0;\npragma solidity;"), vec!["^0.5.0"]);
+  }
+
+  #[test]
+  fn it_should_rewrite_paths_and_preserve_aliases() {
+    let source = "import {A as B} from \"./A.sol\";\nimport * as C from './C.sol'; // import \"./A.sol\";\n";
+    let imports = parse_imports(source);
+    let rewrites = imports.iter()
+      .map(|import| (import, format!("/normalized/{}", &import.path[2..])))
+      .collect::<Vec<(&ImportDirective, String)>>();
+
+    assert_eq!(
+      rewrite_import_paths(source, &rewrites),
+      "import {A as B} from \"/normalized/A.sol\";\nimport * as C from '/normalized/C.sol'; // import \"./A.sol\";\n"
+    );
+  }
+}
@@ -1,99 +1,148 @@
+pub mod artifact;
 pub mod error;
 pub mod support;
+pub mod strategy;
+pub mod standard_json;
+pub mod diagnostics;
+pub mod versions;
+pub mod remappings;
+pub mod watch;
+pub mod sizes;
+mod cache;
+mod imports;
 mod utils;
 
 use crate::config;
 use crate::utils as lib_utils;
-use glob::glob;
+use glob::{glob, Pattern};
 use std::fs;
-use std::process::{Child, Command, Stdio};
-use std::path::PathBuf;
-use std::io::Write;
-use std::collections::HashSet;
-use support::SupportedCompilers;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::thread;
+use artifact::{ArtifactCompiler, ArtifactNames, ContractArtifact};
+use cache::{CompilationUnit, CompileCache, CompilerFingerprint, COMPILE_CACHE_FILE};
+use diagnostics::{CompilerDiagnostic, DiagnosticSeverity, SourceMapping};
+use remappings::Remapping;
+use sizes::{ContractSize, SizeLimits};
+use standard_json::StandardJsonContract;
+use strategy::{CompilationInput, CompilerStrategies, CompilerStrategy};
+use support::{CommandLineCompiler, Solc};
 use utils::{INTERNAL_SOURCE_DIR};
+use versions::{SolcInstallations, VersionPragma};
+use watch::{FileSnapshot, WatchConfig};
 
+const ARTIFACT_EXTENSION_ABI: &str = "abi";
+const ARTIFACT_EXTENSION_BINARY: &str = "bin";
 
-#[derive(Debug)]
+// Compiled contracts by original source file and contract name.
+type SourceContracts = BTreeMap<PathBuf, BTreeMap<String, StandardJsonContract>>;
+
+// Compilation units together with the strategy that compiles them.
+type StrategyGroup<'u> = (Arc<dyn CompilerStrategy>, Vec<&'u CompilationUnit>);
+
+#[derive(Debug, Clone)]
 pub struct CompilerConfig {
   pub compiler: Option<String>,
   pub compiler_options: Option<Vec<String>>,
   pub smart_imports_enabled: bool,
+  /// Compiles all source files, even if they haven't changed since the last compilation.
+  pub force: bool,
+}
+
+/// The result of a successful compilation.
+#[derive(Debug, Default)]
+pub struct CompilerOutput {
+  /// Contracts compiled via the standard JSON interface by source file and contract name.
+  pub contracts: BTreeMap<String, BTreeMap<String, StandardJsonContract>>,
+  /// Warnings and other diagnostics reported by the compiler, referring to the original source files.
+  pub diagnostics: Vec<CompilerDiagnostic>,
+  /// Runtime and initcode sizes of the compiled Smart Contracts.
+  pub contract_sizes: Vec<ContractSize>,
+  /// Output of compilers that are executed with compiler options.
+  pub stdout: Vec<u8>,
+  pub stderr: Vec<u8>,
+  /// Source files that have been compiled.
+  pub compiled_sources: Vec<PathBuf>,
+  /// Source files that haven't been compiled, because they're unchanged since the last compilation.
+  pub cached_sources: Vec<PathBuf>,
 }
 
 pub struct Compiler<'a> {
   config: &'a config::Config,
+  strategies: CompilerStrategies,
 }
 
 impl<'a> Compiler<'a> {
   pub fn new(config: &config::Config) -> Compiler {
+    Compiler::with_strategies(config, CompilerStrategies::default())
+  }
+
+  pub fn with_strategies(config: &'a config::Config, strategies: CompilerStrategies) -> Compiler<'a> {
     Compiler {
-      config
+      config,
+      strategies,
     }
   }
 
-  pub fn normalize_imports(&self) -> Result<Vec<PathBuf>, error::CompilerError> {
+  /// Copies the project's source files and all files they import into the internal
+  /// source directory, rewriting imports so that the compiler can resolve them.
+  ///
+  /// Source files matching `sources.exclude` aren't compiled on their own, but are still
+  /// normalized if they are imported by other source files. Returns the normalized source
+  /// files mapped to their original source files.
+  pub fn normalize_imports(&self) -> Result<SourceMapping, error::CompilerError> {
     let project_config = self.config.read()?;
+    let sources = self.find_source_files(&project_config.sources)?;
+    let remappings = remappings_from(&project_config, &self.config.project_path)?;
+    self.normalize_sources(sources, &remappings)
+  }
+
+  fn normalize_sources(&self, sources: Vec<PathBuf>, remappings: &[Remapping]) -> Result<SourceMapping, error::CompilerError> {
     let destination_root = self.config.vibranium_dir_path.join(INTERNAL_SOURCE_DIR);
 
     if !destination_root.exists() {
       fs::create_dir_all(&destination_root)?;
     }
 
-    let mut unread = project_config.sources.smart_contracts.iter()
-      .map(|pattern| {
-        // `pattern` could use `/` or `\`, decomposing and collecting it normalizes
-        // it into the correct format depending on plattform.
-        let pattern = PathBuf::from(pattern).components().collect::<PathBuf>();
-        let full_pattern = self.config.project_path.join(&pattern);
-        info!("Searching for source files with pattern: {:?}", &full_pattern.display().to_string());
-        full_pattern
-      })
-      .flat_map(|path| glob(&path.display().to_string()).unwrap().filter_map(Result::ok))
-      .map(|path| {
-        // Source file paths for compilation have to be absolute and canonicalized
-        // (e.g. all `..` and `./` etc. removed) otherwise solcjs won't resolve and recognize
-        // the source path properly. For more info see: https://github.com/ethereum/solc-js/issues/377
-        //
-        // In addition, on Windows platforms, the canonicalized path may include a verbatim (`\\?\`).
-        // This breaks compilers (Solc, SolcJS), so we have to strip it out.
-        lib_utils::adjust_canonicalization(&path.canonicalize().unwrap())
-      })
-      .collect::<Vec<PathBuf>>();
-
+    let mut unread = sources;
     let mut seen = unread.iter().cloned().collect::<HashSet<_>>();
-    let mut normalized_imports = HashSet::new();
+    let mut normalized_imports = SourceMapping::new();
 
     while let Some(path) = unread.pop() {
-      if let Ok(mut contents) = fs::read_to_string(&path) {
+      if let Ok(contents) = fs::read_to_string(&path) {
         let destination_path = utils::get_destination_path(&path, &self.config.project_path, &destination_root);
         fs::create_dir_all(destination_path.parent().unwrap())?;
 
-        let imports = utils::extract_imports(&mut contents);
-        let mut normalized_file = fs::File::create(&destination_path)?;
+        let imports = imports::parse_imports(&contents);
+        let mut rewrites = vec![];
 
         info!("Normalizing imports for: {:?}", &path);
-        for import in imports {
-          let resolved_import = utils::resolve_import(&import, &path.parent().unwrap(), &self.config.project_path, &destination_root)?;
+        for import in &imports {
+          let resolved_import = utils::resolve_import(&import.path, &path.parent().unwrap(), &self.config.project_path, &destination_root, remappings)?;
 
-          contents = contents.replace(&import, &resolved_import.2.to_str().unwrap());
+          rewrites.push((import, resolved_import.2.to_string_lossy().to_string()));
           if !seen.contains(&resolved_import.1) {
             unread.push(resolved_import.1.clone());
             seen.insert(resolved_import.1);
           }
         }
-        normalized_file.write_all(contents.as_bytes())?;
-        if !normalized_imports.contains(&destination_path) {
-          normalized_imports.insert(destination_path);
+        let contents = imports::rewrite_import_paths(&contents, &rewrites);
+        // Unchanged files aren't written again, so that their modification time is preserved.
+        if fs::read_to_string(&destination_path).ok().as_ref() != Some(&contents) {
+          fs::write(&destination_path, contents.as_bytes())?;
         }
+        normalized_imports.entry(destination_path).or_insert(path);
       }
     }
-    Ok(normalized_imports.iter().cloned().collect::<Vec<PathBuf>>())
+    Ok(normalized_imports)
   }
 
-  fn find_input_files(&self, patterns: &Vec<String>) -> Vec<String> {
+  fn find_input_files(&self, sources: &config::ProjectSourcesConfig) -> Result<Vec<String>, error::CompilerError> {
+    let exclude_patterns = exclude_patterns_from(sources)?;
     let mut files = vec![];
-    for pattern in patterns {
+    for pattern in &sources.smart_contracts {
       let pattern = PathBuf::from(&pattern);
       let full_pattern = if pattern.starts_with(&self.config.project_path) {
         pattern
@@ -104,110 +153,954 @@ impl<'a> Compiler<'a> {
         glob(&full_pattern.to_str().unwrap())
         .unwrap()
         .filter_map(Result::ok)
+        .filter(|path| !is_excluded(path, &self.config.project_path, &exclude_patterns))
         .map(|path| path.to_string_lossy().to_string())
         .collect::<Vec<String>>()
       );
     }
-    files
+    Ok(files)
   }
 
-  pub fn compile(&self, config: CompilerConfig) -> Result<Child, error::CompilerError> {
+  // Returns the source files that aren't excluded from compilation.
+  fn find_source_files(&self, sources: &config::ProjectSourcesConfig) -> Result<Vec<PathBuf>, error::CompilerError> {
+    Ok(self.find_input_files(sources)?
+      .iter()
+      .map(|path| {
+        // Source file paths for compilation have to be absolute and canonicalized
+        // (e.g. all `..` and `./` etc. removed) otherwise solcjs won't resolve and recognize
+        // the source path properly. For more info see: https://github.com/ethereum/solc-js/issues/377
+        //
+        // In addition, on Windows platforms, the canonicalized path may include a verbatim (`\\?\`).
+        // This breaks compilers (Solc, SolcJS), so we have to strip it out.
+        lib_utils::adjust_canonicalization(PathBuf::from(path).canonicalize().unwrap())
+      })
+      .collect())
+  }
+
+  /// Compiles the project's Smart Contracts with the strategy registered for the configured
+  /// compiler. Compilers without a registered strategy can only be used with compiler options.
+  /// Source files the configured compiler doesn't compile, e.g. Vyper sources in a Solidity
+  /// project, are compiled with the registered strategy that compiles them.
+  ///
+  /// Solidity source files are compiled with the latest solc release installed in
+  /// `~/.vibranium/compilers` that satisfies their version pragmas and `compiler.version`,
+  /// or with the solc executable on the `PATH` if there are none and no version is configured.
+  ///
+  /// Only source files that changed since the last compilation, or that import changed
+  /// files, are compiled, unless `CompilerConfig::force` is set.
+  pub fn compile(&self, config: CompilerConfig) -> Result<CompilerOutput, error::CompilerError> {
     let project_config = self.config.read()?;
-    // `project_config.sources.artifacts` could use `/` or `\`, decomposing and collecting it normalizes
-    // it into the correct format depending on plattform.
-    let artifacts_path = PathBuf::from(&project_config.sources.artifacts).components().collect::<PathBuf>();
-    let artifacts_dir = self.config.project_path.join(artifacts_path);
+    let artifacts_dir = self.artifacts_dir(&project_config);
 
-    let compiler = config.compiler.unwrap_or_else(|| {
+    let compiler = config.compiler.clone().unwrap_or_else(|| {
       match &project_config.compiler {
-        Some(config) => config.cmd.clone().unwrap_or_else(|| SupportedCompilers::Solc.executable()),
-        None => SupportedCompilers::Solc.executable(),
+        Some(config) => config.cmd.clone().unwrap_or_else(|| Solc.name().to_string()),
+        None => Solc.name().to_string(),
       }
     });
 
-    let mut compiler_options = match &config.compiler_options {
-      Some(options) => {
-        match compiler.parse() {
-          Ok(SupportedCompilers::Solc) => lib_utils::merge_cli_options(
-            support::default_options_from(SupportedCompilers::Solc),
-            options.to_vec()
-          ),
-          Ok(SupportedCompilers::SolcJs) => lib_utils::merge_cli_options(
-            support::default_options_from(SupportedCompilers::SolcJs),
-            options.to_vec()
-          ),
-          Err(_err) => options.to_vec(),
-        }
+    let strategy = self.strategies.find(&compiler).unwrap_or_else(|| Arc::new(CommandLineCompiler::new(compiler.clone())));
+    let compiler_options = compiler_options_from(strategy.as_ref(), &config, &project_config);
+    let compiler_version = project_config.compiler.as_ref().and_then(|compiler_config| compiler_config.version.clone());
+    let remappings = remappings_from(&project_config, &self.config.project_path)?;
+    let size_limits = SizeLimits::from_config(project_config.compiler.as_ref());
+
+    let cache_file = self.config.vibranium_dir_path.join(COMPILE_CACHE_FILE);
+    let mut cache = CompileCache::read(&cache_file);
+
+    let units = self.find_source_files(&project_config.sources)?
+      .into_iter()
+      .map(|source| CompilationUnit::new(source, &self.config.project_path, &remappings))
+      .collect::<Result<Vec<CompilationUnit>, error::CompilerError>>()?;
+    cache.retain(&units);
+
+    let mut groups = vec![];
+    for (group_strategy, group_units) in self.group_by_strategy(&strategy, &units) {
+      if group_strategy.name() == Solc.name() && !group_units.is_empty() {
+        groups.extend(self.group_by_solc_release(group_strategy, group_units, compiler_version.as_ref())?);
+      } else {
+        groups.push((group_strategy, group_units));
+      }
+    }
+
+    let mut output = CompilerOutput::default();
+
+    for (group_strategy, group_units) in groups {
+      // Compiler options only apply to the configured compiler.
+      let options = if group_strategy.name() == strategy.name() { compiler_options.clone() } else { vec![] };
+      let fingerprint = CompilerFingerprint {
+        compiler: group_strategy.name().to_string(),
+        version: group_strategy.version(),
+        options: options.clone(),
+        artifacts_dir: artifacts_dir.to_string_lossy().to_string(),
+        smart_imports: config.smart_imports_enabled,
+        remappings: remappings.iter().map(Remapping::to_string).collect(),
+        size_limits: (size_limits.max_contract_size, size_limits.max_initcode_size, size_limits.severity == DiagnosticSeverity::Error),
+      };
+
+      let (outdated, up_to_date): (Vec<&CompilationUnit>, Vec<&CompilationUnit>) = group_units.into_iter().partition(|unit| {
+        config.force || !cache.is_up_to_date(unit, &fingerprint, |name| artifact_exists(&artifacts_dir, name))
+      });
+      output.cached_sources.extend(up_to_date.iter().map(|unit| unit.source.clone()));
+
+      // The configured compiler runs even without source files, so that it can report them missing.
+      if outdated.is_empty() && !units.is_empty() {
+        info!("Skipping compilation with {}, all source files are up to date", group_strategy.name());
+        continue;
       }
-      None => {
-        match project_config.compiler {
-          Some(config) => config.options.unwrap_or_else(|| try_default_options_from(&compiler)),
-          None => try_default_options_from(&compiler)
+
+      let compiled_sources = outdated.iter().map(|unit| unit.source.clone()).collect::<Vec<PathBuf>>();
+      let compiled_files = outdated.iter().flat_map(|unit| unit.files.iter().cloned()).collect::<BTreeSet<PathBuf>>();
+      info!("Compiling source files with {}: {:?}", group_strategy.name(), &compiled_sources);
+
+      let smart_imports_enabled = config.smart_imports_enabled && group_strategy.supports_smart_imports();
+      let artifact_compiler = ArtifactCompiler { name: group_strategy.name().to_string(), version: fingerprint.version.clone() };
+      let result = self.compile_sources(group_strategy.as_ref(), &options, &compiled_sources, smart_imports_enabled, &artifacts_dir, &remappings)
+        .and_then(|(group_output, contracts)| {
+          let contract_sizes = self.contract_sizes(&contracts);
+          let size_diagnostics = contract_sizes.iter().flat_map(|size| size.diagnostics(&size_limits)).collect::<Vec<CompilerDiagnostic>>();
+          // No artifacts are written and source files stay outdated, so that they're checked again
+          // by the next compilation.
+          if size_diagnostics.iter().any(CompilerDiagnostic::is_error) {
+            let mut diagnostics = group_output.diagnostics;
+            diagnostics.extend(size_diagnostics);
+            return Err(error::CompilerError::CompilationFailed(diagnostics));
+          }
+          let artifacts = self.write_artifacts(group_strategy.as_ref(), &compiled_files, &contracts, &artifact_compiler, &artifacts_dir, &mut cache)?;
+          Ok((group_output, artifacts, contract_sizes, size_diagnostics))
+        });
+      let (group_output, artifacts, contract_sizes, size_diagnostics) = match result {
+        Ok(result) => result,
+        Err(err) => {
+          // Keeps source files that have been compiled by other compilers up to date.
+          cache.write(&cache_file)?;
+          return Err(err);
         }
+      };
+
+      for unit in outdated {
+        cache.update(unit, &fingerprint, artifacts.get(&unit.source).cloned().unwrap_or_default());
+      }
+
+      output.contracts.extend(group_output.contracts);
+      output.diagnostics.extend(group_output.diagnostics);
+      output.diagnostics.extend(size_diagnostics);
+      output.contract_sizes.extend(contract_sizes);
+      output.stdout.extend(group_output.stdout);
+      output.stderr.extend(group_output.stderr);
+      output.compiled_sources.extend(compiled_sources);
+    }
+
+    cache.write(&cache_file)?;
+    Ok(output)
+  }
+
+  /// Compiles the project and recompiles it whenever one of the files returned by
+  /// `watched_files` changes. `on_compile` is called with the result of every compilation,
+  /// watching stops once it returns `false`. Only the first compilation is forced.
+  pub fn watch<F>(&self, config: CompilerConfig, watch_config: &WatchConfig, mut on_compile: F) -> Result<(), error::CompilerError>
+    where F: FnMut(Result<CompilerOutput, error::CompilerError>) -> bool
+  {
+    let mut config = config;
+    let mut files = self.watched_files()?;
+    loop {
+      // Taken before compiling, so that changes during compilation cause another compilation.
+      let snapshot = FileSnapshot::new(files);
+      if !on_compile(self.compile(config.clone())) {
+        return Ok(());
       }
+      config.force = false;
+      files = self.wait_for_changes(snapshot, watch_config);
+    }
+  }
+
+  /// Returns the files compilation depends on: the project configuration and the files it
+  /// `extends`, the source files and all files they import, including dependencies in
+  /// `node_modules`.
+  pub fn watched_files(&self) -> Result<BTreeSet<PathBuf>, error::CompilerError> {
+    let project_config = self.config.read()?;
+    let remappings = remappings_from(&project_config, &self.config.project_path)?;
+    let mut files = BTreeSet::new();
+    files.extend(self.config.files()?);
+    for source in self.find_source_files(&project_config.sources)? {
+      files.extend(CompilationUnit::new(source, &self.config.project_path, &remappings)?.files);
+    }
+    Ok(files)
+  }
+
+  /// Returns the source files of the project and all files they import, relative to the project
+  /// if they're inside of it, like the source paths of artifacts.
+  pub fn source_paths(&self) -> Result<BTreeSet<String>, error::CompilerError> {
+    let project_config = self.config.read()?;
+    let remappings = remappings_from(&project_config, &self.config.project_path)?;
+    let mut source_paths = BTreeSet::new();
+    for source in self.find_source_files(&project_config.sources)? {
+      let unit = CompilationUnit::new(source, &self.config.project_path, &remappings)?;
+      source_paths.extend(unit.files.iter().map(|file| artifact::source_path(file, &self.config.project_path)));
+    }
+    Ok(source_paths)
+  }
+
+  /// Returns the qualified names, `<SourcePath>:<ContractName>`, a contract of the given source file
+  /// may have been compiled as, which compilers hash for library placeholders. Besides the source path
+  /// relative to the project, these are the paths passed to compilers: the absolute path of the source
+  /// file, see `find_source_files`, and the path of its normalized copy, see `normalize_sources`.
+  pub fn qualified_names(&self, source_path: &str, contract_name: &str) -> Vec<String> {
+    let path = self.config.project_path.join(source_path);
+    let path = path.canonicalize().map(lib_utils::adjust_canonicalization).unwrap_or(path);
+    let normalized_path = utils::get_destination_path(&path, &self.config.project_path, &self.config.vibranium_dir_path.join(INTERNAL_SOURCE_DIR));
+    vec![source_path.to_string(), path.to_string_lossy().to_string(), normalized_path.to_string_lossy().to_string()]
+      .into_iter()
+      .map(|path| format!("{}:{}", path, contract_name))
+      .collect()
+  }
+
+  // Blocks until watched files changed and haven't changed again for the debounce duration.
+  // Files are looked up again on every check, so that new source files and imports are
+  // watched as well. Returns the watched files.
+  fn wait_for_changes(&self, snapshot: FileSnapshot, watch_config: &WatchConfig) -> BTreeSet<PathBuf> {
+    let current_snapshot = |snapshot: &FileSnapshot| {
+      // While the project can't be read, e.g. due to an invalid configuration, the previous files are watched.
+      FileSnapshot::new(self.watched_files().unwrap_or_else(|_err| snapshot.files()))
     };
 
-    if compiler_options.is_empty() {
-      if let Err(err) = compiler.parse::<SupportedCompilers>() {
-        Err(err)?
+    let mut snapshot = snapshot;
+    loop {
+      thread::sleep(watch_config.poll_interval);
+      let current = current_snapshot(&snapshot);
+      if current != snapshot {
+        info!("Detected changes of watched files");
+        snapshot = current;
+        break;
+      }
+    }
+    loop {
+      thread::sleep(watch_config.debounce);
+      let current = current_snapshot(&snapshot);
+      if current == snapshot {
+        return snapshot.files();
       }
+      snapshot = current;
     }
+  }
 
-    compiler_options.push(artifacts_dir.to_string_lossy().to_string());
+  // Groups compilation units by the strategy that compiles them. The configured compiler's
+  // group always comes first, units none of the strategies compile are part of it.
+  fn group_by_strategy<'u>(&self, strategy: &Arc<dyn CompilerStrategy>, units: &'u [CompilationUnit]) -> Vec<StrategyGroup<'u>> {
+    let mut groups: Vec<StrategyGroup> = vec![(strategy.clone(), vec![])];
+    for unit in units {
+      let unit_strategy = if strategy.compiles(&unit.source) {
+        strategy.clone()
+      } else {
+        self.strategies.find_for_source(&unit.source).unwrap_or_else(|| strategy.clone())
+      };
+      match groups.iter_mut().find(|(group_strategy, _units)| group_strategy.name() == unit_strategy.name()) {
+        Some((_strategy, group_units)) => group_units.push(unit),
+        None => groups.push((unit_strategy, vec![unit])),
+      }
+    }
+    groups
+  }
 
-    let input_files = if !config.smart_imports_enabled {
-      self.find_input_files(&project_config.sources.smart_contracts)
-    } else {
-      match compiler.parse() {
-        Ok(SupportedCompilers::Solc) => {
-          self.normalize_imports()?
-            .iter()
-            .map(|path| path.to_string_lossy().to_string())
-            .collect::<Vec<String>>()
-        },
-        Ok(SupportedCompilers::SolcJs) => {
-          self.normalize_imports()?
-            .iter()
-            .map(|path| path.to_string_lossy().to_string())
-            .collect::<Vec<String>>()
-        },
-        Err(_err) => {
-          self.find_input_files(&project_config.sources.smart_contracts)
-        }
+  // Splits units compiled by solc into groups of the installed solc releases that satisfy
+  // their version pragmas. Without installed releases and configured version, the units
+  // are compiled by the given strategy.
+  fn group_by_solc_release<'u>(&self, strategy: Arc<dyn CompilerStrategy>, units: Vec<&'u CompilationUnit>, version: Option<&String>) -> Result<Vec<StrategyGroup<'u>>, error::CompilerError> {
+    let installations = versions::compilers_dir()
+      .map(|compilers_dir| SolcInstallations::read(&compilers_dir))
+      .unwrap_or_default();
+    if installations.is_empty() && version.is_none() {
+      return Ok(vec![(strategy, units)]);
+    }
+
+    let parse = |pragma: &String| VersionPragma::parse(pragma).map_err(|message| error::CompilerError::InvalidVersionPragma(pragma.clone(), message));
+    let configured_pragma = version.map(parse).transpose()?;
+
+    let mut groups: Vec<(versions::SolcRelease, Vec<&CompilationUnit>)> = vec![];
+    for unit in units {
+      let mut pragmas = unit.version_pragmas.iter().map(parse).collect::<Result<Vec<VersionPragma>, error::CompilerError>>()?;
+      pragmas.extend(configured_pragma.clone());
+
+      let release = installations.resolve(&pragmas).ok_or_else(|| {
+        let source = unit.source.strip_prefix(&self.config.project_path).unwrap_or(&unit.source).to_path_buf();
+        error::CompilerError::NoMatchingSolcVersion(source, pragmas.iter().map(VersionPragma::to_string).collect())
+      })?;
+      info!("Using solc {} for {:?}", release.version, &unit.source);
+
+      match groups.iter_mut().find(|(group_release, _units)| group_release == release) {
+        Some((_release, group_units)) => group_units.push(unit),
+        None => groups.push((release.clone(), vec![unit])),
       }
+    }
+
+    Ok(groups.into_iter()
+      .map(|(release, units)| (Arc::new(release) as Arc<dyn CompilerStrategy>, units))
+      .collect())
+  }
+
+  /// Reads the JSON artifacts of all compiled Smart Contracts, see `artifact::ContractArtifact`.
+  pub fn read_artifacts(&self) -> Result<Vec<ContractArtifact>, error::CompilerError> {
+    let project_config = self.config.read()?;
+    artifact::read_artifacts(&self.artifacts_dir(&project_config))
+  }
+
+  fn artifacts_dir(&self, project_config: &config::ProjectConfig) -> PathBuf {
+    // `project_config.sources.artifacts` could use `/` or `\`, decomposing and collecting it normalizes
+    // it into the correct format depending on plattform.
+    let artifacts_path = PathBuf::from(&project_config.sources.artifacts).components().collect::<PathBuf>();
+    self.config.project_path.join(artifacts_path)
+  }
+
+  // Compiles the given source files. Returns the output together with the compiled Smart
+  // Contracts by original source file.
+  fn compile_sources(&self, strategy: &dyn CompilerStrategy, options: &[String], sources: &[PathBuf], smart_imports_enabled: bool, artifacts_dir: &Path, remappings: &[Remapping]) -> Result<(CompilerOutput, SourceContracts), error::CompilerError> {
+    let (input_files, source_mapping) = self.find_sources(smart_imports_enabled, sources, remappings)?;
+
+    let input = CompilationInput {
+      project_path: &self.config.project_path,
+      artifacts_dir,
+      input_files: &input_files,
+      source_mapping: &source_mapping,
+      options,
+      remappings,
     };
 
-    compiler_options.extend(input_files);
-    compiler_options.insert(0, compiler.to_string());
+    let output = strategy.compile(&input).map_err(|err| {
+      match err {
+        error::CompilerError::CompilationFailed(diagnostics) => {
+          error::CompilerError::CompilationFailed(self.to_diagnostics(diagnostics, &source_mapping))
+        },
+        err => err,
+      }
+    })?;
 
-    let (shell, shell_opt) = if cfg!(target_os = "windows") {
-      ("cmd", "/C")
-    } else {
-      ("sh", "-c")
+    let original_source = |file: &str| {
+      let file = PathBuf::from(file);
+      source_mapping.get(&file).cloned().unwrap_or(file)
     };
+    let mut contracts = SourceContracts::new();
+    for (file, file_contracts) in &output.contracts {
+      let mut file_contracts = file_contracts.clone();
+      // Libraries are linked by the original source files they're defined in.
+      for contract in file_contracts.values_mut() {
+        let link_references = mem::take(&mut contract.evm.bytecode.link_references);
+        contract.evm.bytecode.link_references = link_references.into_iter()
+          .map(|(library_file, libraries)| (original_source(&library_file).to_string_lossy().to_string(), libraries))
+          .collect();
+      }
+      contracts.entry(original_source(file)).or_default().extend(file_contracts);
+    }
+
+    Ok((CompilerOutput {
+      diagnostics: self.to_diagnostics(output.diagnostics, &source_mapping),
+      ..output
+    }, contracts))
+  }
+
+  // Runtime and initcode sizes of the compiled contracts.
+  fn contract_sizes(&self, contracts: &SourceContracts) -> Vec<ContractSize> {
+    contracts.iter()
+      .flat_map(|(source, source_contracts)| {
+        let source_path = artifact::source_path(source, &self.config.project_path);
+        source_contracts.iter()
+          .map(|(name, contract)| ContractSize::new(name, &source_path, contract))
+          .collect::<Vec<ContractSize>>()
+      })
+      .collect()
+  }
+
+  // Writes the artifacts of the compiled contracts via the strategy, together with a JSON artifact
+  // with build metadata for every contract. Artifacts are named as described by `ArtifactNames`,
+  // existing artifacts are renamed if necessary. Returns the artifact names by source file.
+  //
+  // Existing JSON artifacts of all compiled files, including imported ones, are outdated, even if the
+  // compiler wrote its artifacts itself and there are no compiled contracts, e.g. when compiling with
+  // `compiler.options`.
+  fn write_artifacts(&self, strategy: &dyn CompilerStrategy, compiled_files: &BTreeSet<PathBuf>, contracts: &SourceContracts, compiler: &ArtifactCompiler, artifacts_dir: &Path, cache: &mut CompileCache) -> Result<BTreeMap<PathBuf, Vec<String>>, error::CompilerError> {
+    let project_path = &self.config.project_path;
+    let mut compiled = compiled_files.iter()
+      .map(|source| (artifact::source_path(source, project_path), BTreeSet::new()))
+      .collect::<BTreeMap<String, BTreeSet<String>>>();
+    for (source, source_contracts) in contracts {
+      compiled.entry(artifact::source_path(source, project_path)).or_default().extend(source_contracts.keys().cloned());
+    }
+    let existing = artifact::read_named_artifacts(artifacts_dir)?;
+    let artifact_names = ArtifactNames::resolve(&existing, &compiled, |source_path| project_path.join(source_path).exists());
 
-    info!("Compiling project using command: {} {} {}", &shell, &shell_opt, compiler_options.join(" "));
-
-    Command::new(shell)
-      .arg(shell_opt)
-      .arg(&compiler_options.join(" "))
-      .stdout(Stdio::piped())
-      .stderr(Stdio::piped())
-      .spawn()
-      .map_err(|err| {
-        match err.kind() {
-          std::io::ErrorKind::NotFound => error::CompilerError::ExecutableNotFound(err, shell.to_owned()),
-          _ => error::CompilerError::Io(err)
+    for artifact_name in &artifact_names.outdated {
+      // Artifacts written by the compiler itself have just been replaced, so only JSON artifacts are removed.
+      let files = if contracts.is_empty() { vec![ContractArtifact::path(artifacts_dir, artifact_name)] } else { artifact_files(artifacts_dir, artifact_name).to_vec() };
+      for file in files.iter().filter(|file| file.exists()) {
+        fs::remove_file(file)?;
+      }
+    }
+    for (artifact_name, new_artifact_name) in &artifact_names.renames {
+      info!("Renaming artifacts {} to {}, another source file defines a contract of the same name", artifact_name, new_artifact_name);
+      let new_files = artifact_files(artifacts_dir, new_artifact_name);
+      for (file, new_file) in artifact_files(artifacts_dir, artifact_name).iter().zip(new_files.iter()) {
+        if file.exists() {
+          fs::rename(file, new_file)?;
         }
+      }
+      cache.rename_artifact(artifact_name, new_artifact_name);
+    }
+
+    let mut named_output = CompilerOutput::default();
+    let mut artifacts = BTreeMap::new();
+    for (source, source_contracts) in contracts {
+      let source_path = artifact::source_path(source, project_path);
+      let named_contracts = source_contracts.iter()
+        .map(|(name, contract)| (artifact_names.names[&(source_path.clone(), name.clone())].clone(), contract.clone()))
+        .collect::<BTreeMap<String, StandardJsonContract>>();
+      artifacts.insert(source.clone(), named_contracts.keys().cloned().collect::<Vec<String>>());
+      named_output.contracts.insert(source.to_string_lossy().to_string(), named_contracts);
+    }
+    strategy.collect_artifacts(&named_output, artifacts_dir)?;
+    if !contracts.is_empty() {
+      fs::create_dir_all(artifacts_dir)?;
+    }
+
+    for (source, source_contracts) in contracts {
+      let source_path = artifact::source_path(source, project_path);
+      for (name, contract) in source_contracts {
+        let artifact_name = &artifact_names.names[&(source_path.clone(), name.clone())];
+        ContractArtifact::new(name, contract, source, project_path, compiler.clone())?
+          .write(&ContractArtifact::path(artifacts_dir, artifact_name))?;
+      }
+    }
+    Ok(artifacts)
+  }
+
+  // Returns the input files for the compiler together with the mapping of normalized source
+  // files to original source files, which is empty if imports haven't been normalized.
+  fn find_sources(&self, smart_imports_enabled: bool, sources: &[PathBuf], remappings: &[Remapping]) -> Result<(Vec<String>, SourceMapping), error::CompilerError> {
+    if smart_imports_enabled {
+      let source_mapping = self.normalize_sources(sources.to_vec(), remappings)?;
+      let input_files = source_mapping.keys()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect::<Vec<String>>();
+      Ok((input_files, source_mapping))
+    } else {
+      let input_files = sources.iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect::<Vec<String>>();
+      Ok((input_files, SourceMapping::new()))
+    }
+  }
+
+  fn to_diagnostics(&self, diagnostics: Vec<CompilerDiagnostic>, source_mapping: &SourceMapping) -> Vec<CompilerDiagnostic> {
+    diagnostics.into_iter()
+      .map(|mut diagnostic| {
+        diagnostic.map_to_original_source(source_mapping, &self.config.project_path);
+        diagnostic
       })
+      .collect()
   }
+
 }
 
-fn try_default_options_from(compiler: &str) -> Vec<String> {
-  match compiler.parse() {
-    Ok(SupportedCompilers::Solc) => support::default_options_from(SupportedCompilers::Solc),
-    Ok(SupportedCompilers::SolcJs) => support::default_options_from(SupportedCompilers::SolcJs),
-    Err(_err) => vec![],
+// Options specified via `CompilerConfig` are merged with the strategy's default options,
+// while options specified in the project's configuration replace them.
+fn compiler_options_from(strategy: &dyn CompilerStrategy, config: &CompilerConfig, project_config: &config::ProjectConfig) -> Vec<String> {
+  match &config.compiler_options {
+    Some(options) if !options.is_empty() => lib_utils::merge_cli_options(strategy.default_options(), options.to_vec()),
+    _ => {
+      project_config.compiler.as_ref()
+        .and_then(|compiler_config| compiler_config.options.clone())
+        .unwrap_or_default()
+    }
   }
 }
 
+fn remappings_from(project_config: &config::ProjectConfig, project_path: &Path) -> Result<Vec<Remapping>, error::CompilerError> {
+  project_config.compiler.as_ref()
+    .and_then(|compiler_config| compiler_config.remappings.as_ref())
+    .into_iter()
+    .flatten()
+    .map(|remapping| Remapping::parse(remapping).map(|remapping| remapping.resolve(project_path)))
+    .collect()
+}
+
+fn artifact_exists(artifacts_dir: &Path, name: &str) -> bool {
+  artifact_files(artifacts_dir, name).iter().all(|file| file.exists())
+}
+
+// The ABI, bytecode and JSON artifact files of the given artifact name.
+fn artifact_files(artifacts_dir: &Path, name: &str) -> [PathBuf; 3] {
+  [
+    artifacts_dir.join(format!("{}.{}", name, ARTIFACT_EXTENSION_ABI)),
+    artifacts_dir.join(format!("{}.{}", name, ARTIFACT_EXTENSION_BINARY)),
+    ContractArtifact::path(artifacts_dir, name),
+  ]
+}
+
+fn exclude_patterns_from(sources: &config::ProjectSourcesConfig) -> Result<Vec<Pattern>, error::CompilerError> {
+  sources.exclude.iter()
+    .flatten()
+    .map(|pattern| {
+      // Like source patterns, exclude patterns could use `/` or `\`.
+      let normalized_pattern = PathBuf::from(pattern).components().collect::<PathBuf>();
+      Pattern::new(&normalized_pattern.to_string_lossy())
+        .map_err(|err| error::CompilerError::Other(format!("Invalid exclude pattern {:?}: {}", pattern, err)))
+    })
+    .collect()
+}
+
+// Exclude patterns are relative to the project, just like source patterns.
+fn is_excluded(path: &Path, project_path: &Path, exclude_patterns: &[Pattern]) -> bool {
+  let relative_path = path.strip_prefix(project_path).unwrap_or(path);
+  let excluded = exclude_patterns.iter().any(|pattern| pattern.matches_path(relative_path));
+  if excluded {
+    info!("Excluding source file: {:?}", &path);
+  }
+  excluded
+}
+
+#[cfg(test)]
+mod tests {
+
+  extern crate tempfile;
+
+  use std::collections::BTreeMap;
+  use std::fs;
+  use std::path::{Path, PathBuf};
+  use std::sync::Arc;
+  use std::time::Duration;
+  use tempfile::{tempdir, TempDir};
+  use super::{Compiler, CompilerConfig, CompilerOutput};
+  use super::diagnostics::{CompilerDiagnostic, DiagnosticSeverity};
+  use super::error::CompilerError;
+  use super::standard_json::StandardJsonContract;
+  use super::strategy::{CompilationInput, CompilerStrategies, CompilerStrategy};
+  use super::watch::WatchConfig;
+  use crate::config::Config;
+  use crate::deployment::artifacts::ArtifactIndex;
+  use crate::utils::adjust_canonicalization;
+
+  // Compiles every input file with the given extension into a contract named after the file,
+  // whose bytecode is the hex encoded source, and warns about `Token.sol`. With options, the
+  // artifacts are written to the artifacts directory instead, like `solc --abi --bin -o` does.
+  struct TestCompiler {
+    name: &'static str,
+    extension: &'static str,
+  }
+
+  impl CompilerStrategy for TestCompiler {
+    fn name(&self) -> &str {
+      self.name
+    }
+
+    fn compiles(&self, source: &Path) -> bool {
+      source.extension().and_then(|extension| extension.to_str()) == Some(self.extension)
+    }
+
+    fn version(&self) -> Option<String> {
+      Some("1.0.0".to_string())
+    }
+
+    fn supports_smart_imports(&self) -> bool {
+      true
+    }
+
+    fn compile(&self, input: &CompilationInput) -> Result<CompilerOutput, CompilerError> {
+      let mut output = CompilerOutput::default();
+      for file in input.input_files {
+        let name = Path::new(file).file_stem().unwrap().to_string_lossy().to_string();
+        if name == "Token" {
+          output.diagnostics.push(CompilerDiagnostic {
+            severity: DiagnosticSeverity::Warning,
+            file: Some(PathBuf::from(file)),
+            line: Some(1),
+            column: Some(1),
+            message: "Test warning.".to_string(),
+            code: None,
+          });
+        }
+        let mut contract = StandardJsonContract::default();
+        contract.evm.bytecode.object = fs::read(file).unwrap_or_default().iter().map(|byte| format!("{:02x}", byte)).collect();
+        contract.evm.deployed_bytecode.object = contract.evm.bytecode.object.clone();
+        if !input.options.is_empty() {
+          fs::create_dir_all(input.artifacts_dir).unwrap();
+          fs::write(input.artifacts_dir.join(format!("{}.abi", name)), "[]").unwrap();
+          fs::write(input.artifacts_dir.join(format!("{}.bin", name)), &contract.evm.bytecode.object).unwrap();
+          continue;
+        }
+        let mut contracts = BTreeMap::new();
+        contracts.insert(name, contract);
+        output.contracts.insert(file.clone(), contracts);
+      }
+      Ok(output)
+    }
+  }
+
+  const CONFIG: &str = "
+    [sources]
+      artifacts = \"artifacts\"
+      smart_contracts = [\"contracts/**/*.sol\", \"contracts/**/*.vy\"]
+      exclude = [\"contracts/mocks/**\", \"contracts/**/*Test.sol\"]
+  ";
+
+  fn create_test_project() -> Result<(TempDir, Config), Box<dyn std::error::Error>> {
+    let tmp_dir = tempdir()?;
+    let project_path = adjust_canonicalization(tmp_dir.path().canonicalize()?);
+    let config = Config::new(project_path.clone());
+
+    fs::create_dir_all(&config.vibranium_dir_path)?;
+    fs::create_dir_all(project_path.join("contracts").join("mocks"))?;
+    fs::write(&config.config_file, CONFIG)?;
+    fs::write(project_path.join("contracts").join("Token.sol"), "import \"./mocks/TokenMock.sol\";")?;
+    fs::write(project_path.join("contracts").join("TokenTest.sol"), "")?;
+    fs::write(project_path.join("contracts").join("mocks").join("TokenMock.sol"), "")?;
+
+    Ok((tmp_dir, config))
+  }
+
+  fn file_names(paths: Vec<PathBuf>) -> Vec<String> {
+    let mut names = paths.iter().map(|path| path.file_name().unwrap().to_string_lossy().to_string()).collect::<Vec<String>>();
+    names.sort();
+    names
+  }
+
+  #[test]
+  fn it_should_not_find_excluded_input_files() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, config) = create_test_project()?;
+    let compiler = Compiler::new(&config);
+
+    let input_files = compiler.find_input_files(&config.read()?.sources)?;
+    assert_eq!(file_names(input_files.into_iter().map(PathBuf::from).collect()), vec!["Token.sol"]);
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_normalize_excluded_files_only_if_imported() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, config) = create_test_project()?;
+    let compiler = Compiler::new(&config);
+
+    let source_mapping = compiler.normalize_imports()?;
+    assert_eq!(file_names(source_mapping.keys().cloned().collect()), vec!["Token.sol", "TokenMock.sol"]);
+    assert_eq!(file_names(source_mapping.values().cloned().collect()), vec!["Token.sol", "TokenMock.sol"]);
+    assert!(source_mapping.values().all(|path| !path.starts_with(&config.vibranium_dir_path)));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_compile_with_registered_strategy_and_skip_unchanged_sources() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, config) = create_test_project()?;
+    let mut strategies = CompilerStrategies::default();
+    strategies.register(Arc::new(TestCompiler { name: "test-compiler", extension: "sol" }));
+    let compiler = Compiler::with_strategies(&config, strategies);
+    let compiler_config = || CompilerConfig {
+      compiler: Some("test-compiler".to_string()),
+      compiler_options: None,
+      smart_imports_enabled: true,
+      force: false,
+    };
+
+    let output = compiler.compile(compiler_config())?;
+    assert_eq!(file_names(output.compiled_sources), vec!["Token.sol"]);
+    assert_eq!(output.diagnostics[0].file, Some(PathBuf::from("contracts").join("Token.sol")));
+    assert!(config.project_path.join("artifacts").join("Token.abi").exists());
+    assert!(config.project_path.join("artifacts").join("TokenMock.bin").exists());
+
+    let artifacts = compiler.read_artifacts()?;
+    assert_eq!(artifacts.iter().map(|artifact| artifact.contract_name.as_str()).collect::<Vec<&str>>(), vec!["Token", "TokenMock"]);
+    assert_eq!(artifacts[1].source_path, "contracts/mocks/TokenMock.sol");
+    assert_eq!(artifacts[1].compiler.version, Some("1.0.0".to_string()));
+
+    let output = compiler.compile(compiler_config())?;
+    assert!(output.compiled_sources.is_empty());
+    assert_eq!(file_names(output.cached_sources), vec!["Token.sol"]);
+
+    fs::write(config.project_path.join("contracts").join("mocks").join("TokenMock.sol"), "contract TokenMock {}")?;
+    let output = compiler.compile(compiler_config())?;
+    assert_eq!(file_names(output.compiled_sources), vec!["Token.sol"]);
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_qualify_names_by_the_paths_passed_to_compilers() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, config) = create_test_project()?;
+    let compiler = Compiler::new(&config);
+    let sources = compiler.find_source_files(&config.read()?.sources)?;
+    let qualified_names = compiler.qualified_names("contracts/Token.sol", "Token");
+
+    for smart_imports_enabled in &[true, false] {
+      let (input_files, _source_mapping) = compiler.find_sources(*smart_imports_enabled, &sources, &[])?;
+      let token_file = input_files.iter().find(|file| file.ends_with("Token.sol")).unwrap();
+      assert!(qualified_names.contains(&format!("{}:Token", token_file)));
+    }
+    assert!(qualified_names.contains(&"contracts/Token.sol:Token".to_string()));
+    assert_eq!(compiler.source_paths()?.into_iter().collect::<Vec<String>>(), vec!["contracts/Token.sol", "contracts/mocks/TokenMock.sol"]);
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_remove_json_artifacts_when_compiling_with_options() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, config) = create_test_project()?;
+    let mut strategies = CompilerStrategies::default();
+    strategies.register(Arc::new(TestCompiler { name: "test-compiler", extension: "sol" }));
+    let compiler = Compiler::with_strategies(&config, strategies);
+    let compiler_config = |compiler_options: Option<Vec<String>>| CompilerConfig {
+      compiler: Some("test-compiler".to_string()),
+      compiler_options,
+      smart_imports_enabled: true,
+      force: false,
+    };
+    let artifacts_dir = config.project_path.join("artifacts");
+
+    compiler.compile(compiler_config(None))?;
+    assert!(artifacts_dir.join("Token.json").exists());
+
+    assert!(artifacts_dir.join("TokenMock.json").exists());
+
+    fs::write(config.project_path.join("contracts").join("mocks").join("TokenMock.sol"), "{}")?;
+    compiler.compile(compiler_config(Some(vec!["--abi".to_string()])))?;
+    assert!(compiler.read_artifacts()?.is_empty());
+    assert!(artifacts_dir.join("Token.bin").exists());
+
+    let artifact = ArtifactIndex::read(&artifacts_dir)?.find("TokenMock")?.unwrap().load()?;
+    assert_eq!(artifact.path, artifacts_dir.join("TokenMock.bin"));
+    assert_eq!(artifact.bytecode, "7b7d");
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_disambiguate_artifacts_of_contracts_with_the_same_name() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, config) = create_test_project()?;
+    let mut strategies = CompilerStrategies::default();
+    strategies.register(Arc::new(TestCompiler { name: "test-compiler", extension: "sol" }));
+    let compiler = Compiler::with_strategies(&config, strategies);
+    let compiler_config = || CompilerConfig {
+      compiler: Some("test-compiler".to_string()),
+      compiler_options: None,
+      smart_imports_enabled: true,
+      force: false,
+    };
+    let artifacts_dir = config.project_path.join("artifacts");
+
+    compiler.compile(compiler_config())?;
+    assert!(artifacts_dir.join("Token.json").exists());
+
+    fs::create_dir_all(config.project_path.join("contracts").join("legacy"))?;
+    fs::write(config.project_path.join("contracts").join("legacy").join("Token.sol"), "")?;
+    let output = compiler.compile(compiler_config())?;
+    assert_eq!(file_names(output.compiled_sources), vec!["Token.sol"]);
+    assert!(!artifacts_dir.join("Token.abi").exists());
+    assert!(artifacts_dir.join("Token-contracts_Token_sol.abi").exists());
+    assert!(artifacts_dir.join("Token-contracts_legacy_Token_sol.bin").exists());
+
+    let artifacts = compiler.read_artifacts()?;
+    let token_sources = artifacts.iter()
+      .filter(|artifact| artifact.contract_name == "Token")
+      .map(|artifact| artifact.source_path.as_str())
+      .collect::<Vec<&str>>();
+    assert_eq!(token_sources.len(), 2);
+    assert!(token_sources.contains(&"contracts/Token.sol") && token_sources.contains(&"contracts/legacy/Token.sol"));
+
+    let output = compiler.compile(compiler_config())?;
+    assert!(output.compiled_sources.is_empty());
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_check_contract_sizes_against_configured_limits() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, config) = create_test_project()?;
+    let mut strategies = CompilerStrategies::default();
+    strategies.register(Arc::new(TestCompiler { name: "test-compiler", extension: "sol" }));
+    let compiler = Compiler::with_strategies(&config, strategies);
+    let compiler_config = || CompilerConfig {
+      compiler: Some("test-compiler".to_string()),
+      compiler_options: None,
+      smart_imports_enabled: false,
+      force: false,
+    };
+    fs::write(config.project_path.join("contracts").join("Token.sol"), "contract Token { uint256 public totalSupply; }")?;
+    fs::write(&config.config_file, format!("{}\n[compiler]\nmax_contract_size = 32\nfail_on_size_limit = true\n", CONFIG))?;
+    match compiler.compile(compiler_config()) {
+      Err(CompilerError::CompilationFailed(diagnostics)) => assert!(diagnostics.iter().any(|diagnostic| diagnostic.is_error())),
+      _ => panic!("Expected compilation to fail"),
+    }
+    assert!(!config.project_path.join("artifacts").exists());
+    // Source files exceeding the limits aren't considered up to date.
+    assert!(compiler.compile(compiler_config()).is_err());
+
+    fs::write(&config.config_file, format!("{}\n[compiler]\nmax_contract_size = 32\n", CONFIG))?;
+    let output = compiler.compile(compiler_config())?;
+    let sizes = output.contract_sizes.iter().map(|size| (size.contract_name.as_str(), size.runtime_size, size.initcode_size)).collect::<Vec<_>>();
+    assert_eq!(sizes, vec![("Token", Some(46), Some(46))]);
+    let size_warnings = output.diagnostics.iter().filter(|diagnostic| diagnostic.message.contains("runtime size")).collect::<Vec<_>>();
+    assert_eq!(size_warnings.len(), 1);
+    assert_eq!(size_warnings[0].severity, DiagnosticSeverity::Warning);
+    assert_eq!(size_warnings[0].file, Some(PathBuf::from("contracts/Token.sol")));
+    assert!(config.project_path.join("artifacts").join("Token.json").exists());
+
+    fs::write(&config.config_file, CONFIG)?;
+    let output = compiler.compile(compiler_config())?;
+    assert_eq!(file_names(output.compiled_sources), vec!["Token.sol"]);
+    assert!(output.diagnostics.iter().all(|diagnostic| !diagnostic.message.contains("runtime size")));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_compile_sources_of_the_active_environment() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, mut config) = create_test_project()?;
+    fs::write(&config.config_file, format!("{}
+    [environments.staging.sources]
+      artifacts = \"build/staging\"
+      exclude = [\"contracts/Token.sol\"]
+    ", CONFIG))?;
+    fs::write(config.project_path.join("contracts").join("Ownable.sol"), "")?;
+    config.environment = Some("staging".to_string());
+
+    let mut strategies = CompilerStrategies::default();
+    strategies.register(Arc::new(TestCompiler { name: "test-compiler", extension: "sol" }));
+    let compiler = Compiler::with_strategies(&config, strategies);
+    let output = compiler.compile(CompilerConfig {
+      compiler: Some("test-compiler".to_string()),
+      compiler_options: None,
+      smart_imports_enabled: true,
+      force: false,
+    })?;
+
+    assert_eq!(file_names(output.compiled_sources), vec!["Ownable.sol"]);
+    assert!(config.project_path.join("build").join("staging").join("Ownable.json").exists());
+    assert!(!config.project_path.join("artifacts").exists());
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_dispatch_source_files_to_the_strategy_that_compiles_them() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, config) = create_test_project()?;
+    fs::write(config.project_path.join("contracts").join("Vault.vy"), "")?;
+    let mut strategies = CompilerStrategies::default();
+    strategies.register(Arc::new(TestCompiler { name: "test-compiler", extension: "sol" }));
+    strategies.register(Arc::new(TestCompiler { name: "test-vyper", extension: "vy" }));
+    let compiler = Compiler::with_strategies(&config, strategies);
+    let compiler_config = |force| CompilerConfig {
+      compiler: Some("test-compiler".to_string()),
+      compiler_options: None,
+      smart_imports_enabled: true,
+      force,
+    };
+
+    let output = compiler.compile(compiler_config(false))?;
+    assert_eq!(file_names(output.compiled_sources), vec!["Token.sol", "Vault.vy"]);
+    assert!(config.project_path.join("artifacts").join("Vault.abi").exists());
+    assert!(output.contracts.keys().any(|file| file.ends_with("Vault.vy")));
+
+    fs::write(config.project_path.join("contracts").join("Vault.vy"), "# changed")?;
+    let output = compiler.compile(compiler_config(false))?;
+    assert_eq!(file_names(output.compiled_sources), vec!["Vault.vy"]);
+    assert_eq!(file_names(output.cached_sources), vec!["Token.sol"]);
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_normalize_remapped_imports() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, config) = create_test_project()?;
+    fs::write(&config.config_file, format!("{}\n[compiler]\n  remappings = [\"@lib/=lib/\"]", CONFIG))?;
+    fs::create_dir_all(config.project_path.join("lib"))?;
+    fs::write(config.project_path.join("lib").join("Math.sol"), "library Math {}")?;
+    fs::write(config.project_path.join("contracts").join("Token.sol"), "import \"@lib/Math.sol\";")?;
+    let compiler = Compiler::new(&config);
+
+    let source_mapping = compiler.normalize_imports()?;
+    let math = source_mapping.iter().find(|(_normalized, original)| original.ends_with("Math.sol")).unwrap();
+    assert_eq!(math.1, &config.project_path.join("lib").join("Math.sol"));
+
+    let token = source_mapping.keys().find(|normalized| normalized.ends_with("Token.sol")).unwrap();
+    assert_eq!(fs::read_to_string(token)?, format!("import \"{}\";", math.0.to_string_lossy()));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_recompile_when_imported_files_change() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, config) = create_test_project()?;
+    let mut strategies = CompilerStrategies::default();
+    strategies.register(Arc::new(TestCompiler { name: "test-compiler", extension: "sol" }));
+    let compiler = Compiler::with_strategies(&config, strategies);
+    let compiler_config = CompilerConfig {
+      compiler: Some("test-compiler".to_string()),
+      compiler_options: None,
+      smart_imports_enabled: true,
+      force: false,
+    };
+    let watch_config = WatchConfig {
+      poll_interval: Duration::from_millis(10),
+      debounce: Duration::from_millis(10),
+    };
+
+    let watched_files = compiler.watched_files()?;
+    assert!(watched_files.contains(&config.config_file));
+    assert_eq!(file_names(watched_files.into_iter().filter(|file| file.starts_with(config.project_path.join("contracts"))).collect()), vec!["Token.sol", "TokenMock.sol"]);
+
+    let mut compilations = vec![];
+    compiler.watch(compiler_config, &watch_config, |result| {
+      compilations.push(file_names(result.unwrap().compiled_sources));
+      if compilations.len() == 1 {
+        fs::write(config.project_path.join("contracts").join("mocks").join("TokenMock.sol"), "contract TokenMock {}").unwrap();
+      }
+      compilations.len() < 2
+    })?;
+    assert_eq!(compilations, vec![vec!["Token.sol"], vec!["Token.sol"]]);
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_watch_extended_config_files() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, config) = create_test_project()?;
+    let base_config_file = config.project_path.join("base.toml");
+    fs::write(&base_config_file, CONFIG)?;
+    fs::write(&config.config_file, "extends = \"base.toml\"\n")?;
+
+    let watched_files = Compiler::new(&config).watched_files()?;
+    assert!(watched_files.contains(&config.config_file));
+    assert!(watched_files.contains(&base_config_file));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_require_options_for_compilers_without_strategy() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, config) = create_test_project()?;
+    let compiler = Compiler::new(&config);
+
+    let result = compiler.compile(CompilerConfig {
+      compiler: Some("unsupported".to_string()),
+      compiler_options: None,
+      smart_imports_enabled: true,
+      force: false,
+    });
+
+    match result {
+      Err(CompilerError::UnsupportedStrategy) => (),
+      other => panic!("expected unsupported strategy error, got {:?}", other),
+    }
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+}
@@ -0,0 +1,104 @@
+use super::error::CompilerError;
+
+use std::fmt;
+use std::path::Path;
+
+/// An import remapping as supported by solc, `[context:]prefix=target`. Imports starting
+/// with `prefix` in files within `context` are resolved by replacing the prefix with `target`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Remapping {
+  pub context: Option<String>,
+  pub prefix: String,
+  pub target: String,
+}
+
+impl Remapping {
+  pub fn parse(remapping: &str) -> Result<Remapping, CompilerError> {
+    let invalid = || CompilerError::InvalidRemapping(remapping.to_string());
+    let (context_and_prefix, target) = remapping.split_once('=').ok_or_else(invalid)?;
+    let (context, prefix) = match context_and_prefix.split_once(':') {
+      Some((context, prefix)) => (Some(context.to_string()).filter(|context| !context.is_empty()), prefix),
+      None => (None, context_and_prefix),
+    };
+    if prefix.is_empty() || target.is_empty() {
+      return Err(invalid());
+    }
+    Ok(Remapping { context, prefix: prefix.to_string(), target: target.to_string() })
+  }
+
+  /// Resolves relative contexts and targets against the project path, because the compiler
+  /// isn't necessarily run from within the project.
+  pub fn resolve(self, project_path: &Path) -> Remapping {
+    let resolve = |path: String| {
+      if Path::new(&path).is_absolute() {
+        path
+      } else {
+        format!("{}/{}", project_path.to_string_lossy().trim_end_matches('/'), path.trim_start_matches("./"))
+      }
+    };
+    Remapping {
+      context: self.context.map(resolve),
+      prefix: self.prefix,
+      target: resolve(self.target),
+    }
+  }
+}
+
+impl fmt::Display for Remapping {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match &self.context {
+      Some(context) => write!(f, "{}:{}={}", context, self.prefix, self.target),
+      None => write!(f, "{}={}", self.prefix, self.target),
+    }
+  }
+}
+
+/// Remaps an import of the given file like solc does. The remapping with the longest context
+/// and, among those, the longest prefix wins. Returns `None` if no remapping applies.
+pub fn remap(remappings: &[Remapping], import: &str, file: &Path) -> Option<String> {
+  let file = file.to_string_lossy();
+  remappings.iter()
+    .filter(|remapping| import.starts_with(&remapping.prefix))
+    .filter(|remapping| {
+      match &remapping.context {
+        Some(context) => file.starts_with(context.as_str()),
+        None => true,
+      }
+    })
+    .max_by_key(|remapping| (remapping.context.as_ref().map_or(0, String::len), remapping.prefix.len()))
+    .map(|remapping| format!("{}{}", remapping.target, &import[remapping.prefix.len()..]))
+}
+
+#[cfg(test)]
+mod tests {
+
+  use std::path::Path;
+  use super::{remap, Remapping};
+
+  #[test]
+  fn it_should_parse_and_resolve_remappings() {
+    let remapping = Remapping::parse("@oz/=lib/openzeppelin/contracts/").unwrap().resolve(Path::new("/project"));
+    assert_eq!(remapping.to_string(), "@oz/=/project/lib/openzeppelin/contracts/");
+
+    let remapping = Remapping::parse("vendor:math/=/opt/math/").unwrap().resolve(Path::new("/project"));
+    assert_eq!(remapping.to_string(), "/project/vendor:math/=/opt/math/");
+
+    assert!(Remapping::parse("@oz/").is_err());
+    assert!(Remapping::parse("=lib/").is_err());
+  }
+
+  #[test]
+  fn it_should_remap_imports_with_longest_matching_remapping() {
+    let remappings = vec![
+      Remapping::parse("@oz/=/project/lib/oz/").unwrap(),
+      Remapping::parse("@oz/token/=/project/lib/oz-token/").unwrap(),
+      Remapping::parse("/project/vendor:@oz/=/project/vendor/oz/").unwrap(),
+    ];
+    let file = Path::new("/project/contracts/Token.sol");
+
+    assert_eq!(remap(&remappings, "@oz/math/Math.sol", file), Some("/project/lib/oz/math/Math.sol".to_string()));
+    assert_eq!(remap(&remappings, "@oz/token/ERC20.sol", file), Some("/project/lib/oz-token/ERC20.sol".to_string()));
+    assert_eq!(remap(&remappings, "@oz/math/Math.sol", Path::new("/project/vendor/Lib.sol")), Some("/project/vendor/oz/math/Math.sol".to_string()));
+    assert_eq!(remap(&remappings, "./Ownable.sol", file), None);
+  }
+}
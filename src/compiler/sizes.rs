@@ -0,0 +1,173 @@
+use super::diagnostics::{CompilerDiagnostic, DiagnosticSeverity};
+use super::standard_json::StandardJsonContract;
+use crate::config::ProjectCmdExecutionConfig;
+
+use std::fmt;
+use std::path::PathBuf;
+
+/// Maximum size of deployed bytecode in bytes, as introduced by EIP-170.
+pub const DEFAULT_MAX_CONTRACT_SIZE: usize = 24_576;
+/// Maximum size of initcode in bytes, as introduced by EIP-3860.
+pub const DEFAULT_MAX_INITCODE_SIZE: usize = 49_152;
+
+/// Size thresholds Smart Contracts are checked against, configured by `compiler.max_contract_size`,
+/// `compiler.max_initcode_size` and `compiler.fail_on_size_limit`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SizeLimits {
+  pub max_contract_size: usize,
+  pub max_initcode_size: usize,
+  /// Severity of compiler diagnostics about exceeded limits.
+  pub severity: DiagnosticSeverity,
+}
+
+impl SizeLimits {
+  pub fn from_config(config: Option<&ProjectCmdExecutionConfig>) -> SizeLimits {
+    let default = SizeLimits::default();
+    match config {
+      Some(config) => SizeLimits {
+        max_contract_size: config.max_contract_size.unwrap_or(default.max_contract_size),
+        max_initcode_size: config.max_initcode_size.unwrap_or(default.max_initcode_size),
+        severity: if config.fail_on_size_limit.unwrap_or(false) { DiagnosticSeverity::Error } else { DiagnosticSeverity::Warning },
+      },
+      None => default,
+    }
+  }
+
+  /// Returns the limits exceeded by the given sizes in bytes. Unknown sizes aren't checked.
+  pub fn check(&self, runtime_size: Option<usize>, initcode_size: Option<usize>) -> Vec<SizeLimitViolation> {
+    let checks = [
+      (SizeKind::Runtime, runtime_size, self.max_contract_size),
+      (SizeKind::Initcode, initcode_size, self.max_initcode_size),
+    ];
+    checks.iter()
+      .filter_map(|(kind, size, limit)| size.filter(|size| size > limit).map(|size| SizeLimitViolation { kind: *kind, size, limit: *limit }))
+      .collect()
+  }
+}
+
+impl Default for SizeLimits {
+  fn default() -> Self {
+    SizeLimits {
+      max_contract_size: DEFAULT_MAX_CONTRACT_SIZE,
+      max_initcode_size: DEFAULT_MAX_INITCODE_SIZE,
+      severity: DiagnosticSeverity::Warning,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SizeKind {
+  /// The deployed bytecode, which is stored on chain.
+  Runtime,
+  /// The bytecode that is sent to create a Smart Contract, including constructor arguments.
+  Initcode,
+}
+
+impl fmt::Display for SizeKind {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      SizeKind::Runtime => write!(f, "runtime size"),
+      SizeKind::Initcode => write!(f, "initcode size"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SizeLimitViolation {
+  pub kind: SizeKind,
+  pub size: usize,
+  pub limit: usize,
+}
+
+impl fmt::Display for SizeLimitViolation {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} of {} bytes exceeds the limit of {} bytes", self.kind, self.size, self.limit)
+  }
+}
+
+/// Runtime and initcode size of a compiled Smart Contract in bytes. Sizes are unknown
+/// if the compiler didn't emit the corresponding bytecode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContractSize {
+  pub contract_name: String,
+  /// Source file of the Smart Contract, relative to the project.
+  pub source_path: String,
+  pub runtime_size: Option<usize>,
+  pub initcode_size: Option<usize>,
+}
+
+impl ContractSize {
+  pub fn new(contract_name: &str, source_path: &str, contract: &StandardJsonContract) -> ContractSize {
+    ContractSize {
+      contract_name: contract_name.to_string(),
+      source_path: source_path.to_string(),
+      runtime_size: bytecode_size(&contract.evm.deployed_bytecode.object),
+      initcode_size: bytecode_size(&contract.evm.bytecode.object),
+    }
+  }
+
+  /// Returns a diagnostic for every limit the Smart Contract exceeds.
+  pub fn diagnostics(&self, limits: &SizeLimits) -> Vec<CompilerDiagnostic> {
+    limits.check(self.runtime_size, self.initcode_size)
+      .into_iter()
+      .map(|violation| CompilerDiagnostic {
+        severity: limits.severity,
+        file: Some(PathBuf::from(&self.source_path)),
+        line: None,
+        column: None,
+        message: format!("Smart Contract '{}' can't be deployed, its {}", self.contract_name, violation),
+        code: None,
+      })
+      .collect()
+  }
+}
+
+/// Returns the size in bytes of hex encoded bytecode, or `None` if there's no bytecode.
+/// Library placeholders take up the size of the address they're replaced with.
+pub fn bytecode_size(bytecode: &str) -> Option<usize> {
+  let bytecode = bytecode.trim();
+  let bytecode = bytecode.trim_start_matches("0x");
+  if bytecode.is_empty() {
+    None
+  } else {
+    Some(bytecode.len() / 2)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::{bytecode_size, ContractSize, SizeLimits, DEFAULT_MAX_CONTRACT_SIZE};
+  use crate::compiler::diagnostics::DiagnosticSeverity;
+  use crate::compiler::standard_json::StandardJsonContract;
+
+  #[test]
+  fn it_should_compute_sizes_of_hex_encoded_bytecode() {
+    assert_eq!(bytecode_size("0x6080604052"), Some(5));
+    assert_eq!(bytecode_size("6080604052\n"), Some(5));
+    assert_eq!(bytecode_size("0x"), None);
+    assert_eq!(bytecode_size(""), None);
+  }
+
+  #[test]
+  fn it_should_report_smart_contracts_exceeding_size_limits() {
+    let mut contract = StandardJsonContract::default();
+    contract.evm.deployed_bytecode.object = "00".repeat(DEFAULT_MAX_CONTRACT_SIZE + 1);
+    contract.evm.bytecode.object = "00".repeat(DEFAULT_MAX_CONTRACT_SIZE + 32);
+    let size = ContractSize::new("Token", "contracts/Token.sol", &contract);
+    assert_eq!(size.runtime_size, Some(DEFAULT_MAX_CONTRACT_SIZE + 1));
+
+    let diagnostics = size.diagnostics(&SizeLimits::default());
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, DiagnosticSeverity::Warning);
+    assert_eq!(diagnostics[0].message, "Smart Contract 'Token' can't be deployed, its runtime size of 24577 bytes exceeds the limit of 24576 bytes");
+
+    let limits = SizeLimits { max_initcode_size: 1024, severity: DiagnosticSeverity::Error, ..SizeLimits::default() };
+    let diagnostics = size.diagnostics(&limits);
+    assert_eq!(diagnostics.len(), 2);
+    assert!(diagnostics.iter().all(|diagnostic| diagnostic.is_error()));
+
+    let limits = SizeLimits { max_contract_size: DEFAULT_MAX_CONTRACT_SIZE * 2, ..SizeLimits::default() };
+    assert!(size.diagnostics(&limits).is_empty());
+  }
+}
@@ -0,0 +1,242 @@
+use super::error::CompilerError;
+
+use serde_json;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+pub const STANDARD_JSON_OPTION: &str = "--standard-json";
+pub const SOLIDITY_LANGUAGE: &str = "Solidity";
+pub const SEVERITY_ERROR: &str = "error";
+
+const OUTPUT_SELECTION: [&str; 9] = [
+  "abi",
+  "metadata",
+  "storageLayout",
+  "evm.bytecode.object",
+  "evm.bytecode.sourceMap",
+  "evm.bytecode.linkReferences",
+  "evm.deployedBytecode.object",
+  "evm.deployedBytecode.sourceMap",
+  "evm.methodIdentifiers",
+];
+
+/// Input of a compiler that supports the standard JSON interface, such as `solc --standard-json`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StandardJsonInput {
+  pub language: String,
+  pub sources: BTreeMap<String, StandardJsonSource>,
+  pub settings: StandardJsonSettings,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StandardJsonSource {
+  pub content: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StandardJsonSettings {
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub remappings: Vec<String>,
+  #[serde(rename = "outputSelection")]
+  pub output_selection: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+}
+
+impl StandardJsonInput {
+  /// Creates the input for the given source files. Sources are identified by their path,
+  /// so that imports between them are resolved by the compiler.
+  pub fn from_files(files: &[PathBuf], remappings: Vec<String>) -> Result<StandardJsonInput, CompilerError> {
+    let mut sources = BTreeMap::new();
+    for file in files {
+      sources.insert(file.to_string_lossy().to_string(), StandardJsonSource {
+        content: fs::read_to_string(file)?,
+      });
+    }
+
+    let mut contract_selection = BTreeMap::new();
+    contract_selection.insert("*".to_string(), OUTPUT_SELECTION.iter().map(|output| output.to_string()).collect());
+    let mut output_selection = BTreeMap::new();
+    output_selection.insert("*".to_string(), contract_selection);
+
+    Ok(StandardJsonInput {
+      language: SOLIDITY_LANGUAGE.to_string(),
+      sources,
+      settings: StandardJsonSettings {
+        remappings,
+        output_selection,
+      },
+    })
+  }
+}
+
+/// Output of a compiler that supports the standard JSON interface.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct StandardJsonOutput {
+  #[serde(default)]
+  pub errors: Vec<StandardJsonError>,
+  /// Compiled contracts by source file and contract name.
+  #[serde(default)]
+  pub contracts: BTreeMap<String, BTreeMap<String, StandardJsonContract>>,
+}
+
+impl StandardJsonOutput {
+  pub fn parse(output: &[u8]) -> Result<StandardJsonOutput, CompilerError> {
+    serde_json::from_slice(output).map_err(|err| {
+      CompilerError::Other(format!("Couldn't parse compiler output: {}\n{}", err, String::from_utf8_lossy(output)))
+    })
+  }
+
+  pub fn has_errors(&self) -> bool {
+    self.errors.iter().any(StandardJsonError::is_error)
+  }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StandardJsonError {
+  pub severity: String,
+  #[serde(rename = "type")]
+  pub kind: String,
+  #[serde(default)]
+  pub component: String,
+  pub message: String,
+  #[serde(rename = "formattedMessage")]
+  pub formatted_message: Option<String>,
+  #[serde(rename = "sourceLocation")]
+  pub source_location: Option<StandardJsonSourceLocation>,
+  #[serde(rename = "errorCode")]
+  pub error_code: Option<String>,
+}
+
+impl StandardJsonError {
+  pub fn is_error(&self) -> bool {
+    self.severity == SEVERITY_ERROR
+  }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StandardJsonSourceLocation {
+  pub file: String,
+  pub start: i64,
+  pub end: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct StandardJsonContract {
+  #[serde(default)]
+  pub abi: serde_json::Value,
+  #[serde(default)]
+  pub metadata: Option<String>,
+  #[serde(rename = "storageLayout", default)]
+  pub storage_layout: Option<serde_json::Value>,
+  #[serde(default)]
+  pub evm: StandardJsonEvm,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct StandardJsonEvm {
+  #[serde(default)]
+  pub bytecode: StandardJsonBytecode,
+  #[serde(rename = "deployedBytecode", default)]
+  pub deployed_bytecode: StandardJsonBytecode,
+  #[serde(rename = "methodIdentifiers", default)]
+  pub method_identifiers: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct StandardJsonBytecode {
+  #[serde(default)]
+  pub object: String,
+  #[serde(rename = "sourceMap", default)]
+  pub source_map: Option<String>,
+  #[serde(rename = "linkReferences", default)]
+  pub link_references: BTreeMap<String, BTreeMap<String, Vec<StandardJsonLinkReference>>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StandardJsonLinkReference {
+  pub start: usize,
+  pub length: usize,
+}
+
+#[cfg(test)]
+mod tests {
+
+  extern crate tempfile;
+
+  use std::fs;
+  use tempfile::tempdir;
+  use super::{StandardJsonInput, StandardJsonOutput};
+
+  const OUTPUT: &str = r#"{
+    "errors": [
+      {
+        "component": "general",
+        "errorCode": "2072",
+        "formattedMessage": "Warning: Unused local variable.",
+        "message": "Unused local variable.",
+        "severity": "warning",
+        "sourceLocation": { "end": 120, "file": "/project/contracts/Token.sol", "start": 110 },
+        "type": "Warning"
+      }
+    ],
+    "contracts": {
+      "/project/contracts/Token.sol": {
+        "Token": {
+          "abi": [{ "inputs": [], "name": "totalSupply", "outputs": [], "stateMutability": "view", "type": "function" }],
+          "metadata": "{}",
+          "storageLayout": { "storage": [], "types": null },
+          "evm": {
+            "bytecode": { "object": "6080", "sourceMap": "1:2:0:-:0", "linkReferences": {} },
+            "deployedBytecode": { "object": "6081", "sourceMap": "3:4:0:-:0" },
+            "methodIdentifiers": { "totalSupply()": "18160ddd" }
+          }
+        }
+      }
+    },
+    "sources": { "/project/contracts/Token.sol": { "id": 0 } }
+  }"#;
+
+  #[test]
+  fn it_should_build_input_from_source_files() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = tempdir()?;
+    let file = tmp_dir.path().join("Token.sol");
+    fs::write(&file, "contract Token {}")?;
+
+    let input = StandardJsonInput::from_files(std::slice::from_ref(&file), vec![])?;
+    let json = serde_json::to_value(&input)?;
+
+    assert_eq!(json["language"], "Solidity");
+    assert_eq!(json["sources"][file.to_string_lossy().as_ref()]["content"], "contract Token {}");
+    assert!(json["settings"].get("remappings").is_none());
+    assert!(json["settings"]["outputSelection"]["*"]["*"].as_array().unwrap().contains(&serde_json::Value::from("evm.deployedBytecode.object")));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_parse_contracts_and_errors_from_output() {
+    let output = StandardJsonOutput::parse(OUTPUT.as_bytes()).unwrap();
+    let contract = &output.contracts["/project/contracts/Token.sol"]["Token"];
+
+    assert!(!output.has_errors());
+    assert_eq!(output.errors[0].error_code, Some("2072".to_string()));
+    assert_eq!(contract.evm.bytecode.object, "6080");
+    assert_eq!(contract.evm.deployed_bytecode.object, "6081");
+    assert_eq!(contract.evm.deployed_bytecode.source_map, Some("3:4:0:-:0".to_string()));
+    assert_eq!(contract.evm.method_identifiers["totalSupply()"], "18160ddd");
+    assert!(contract.storage_layout.is_some());
+  }
+
+  #[test]
+  fn it_should_detect_errors_in_output() {
+    let output = StandardJsonOutput::parse(br#"{ "errors": [{ "severity": "error", "type": "JSONError", "message": "No input sources specified." }] }"#).unwrap();
+    assert!(output.has_errors());
+    assert!(output.contracts.is_empty());
+  }
+
+  #[test]
+  fn it_should_fail_for_invalid_output() {
+    assert!(StandardJsonOutput::parse(b"Error: unknown option").is_err());
+  }
+}
@@ -0,0 +1,162 @@
+use super::error::CompilerError;
+use super::diagnostics::SourceMapping;
+use super::remappings::Remapping;
+use super::support::{Solc, SolcJs, Vyper};
+use super::{CompilerOutput, ARTIFACT_EXTENSION_ABI, ARTIFACT_EXTENSION_BINARY};
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+
+const VERSION_OPTION: &str = "--version";
+
+/// Everything a compiler strategy needs to compile a set of source files.
+#[derive(Debug)]
+pub struct CompilationInput<'a> {
+  pub project_path: &'a Path,
+  pub artifacts_dir: &'a Path,
+  /// Source files to compile. These are the normalized source files, if the strategy
+  /// supports smart imports and they are enabled.
+  pub input_files: &'a [String],
+  pub source_mapping: &'a SourceMapping,
+  /// Options specified via `CompilerConfig::compiler_options` or `compiler.options`,
+  /// merged with the strategy's default options if specified via `CompilerConfig`.
+  /// Empty if no options are specified.
+  pub options: &'a [String],
+  /// Import remappings of `compiler.remappings`, with targets resolved against the project path.
+  pub remappings: &'a [Remapping],
+}
+
+/// Describes how Vibranium discovers and runs a compiler. Solc and SolcJs are supported
+/// out of the box, other compilers can be supported by registering a strategy via
+/// `Vibranium::register_compiler_strategy`.
+pub trait CompilerStrategy: Send + Sync {
+  /// The name the compiler is selected with, via `compiler.cmd` or `CompilerConfig::compiler`.
+  fn name(&self) -> &str;
+
+  /// The executable that is run to compile source files.
+  fn executable(&self) -> String {
+    self.name().to_string()
+  }
+
+  /// Options that are merged with the options specified via `CompilerConfig::compiler_options`.
+  fn default_options(&self) -> Vec<String> {
+    vec![]
+  }
+
+  /// Whether the strategy compiles the given source file, e.g. based on its extension.
+  /// Source files the configured compiler doesn't compile are dispatched to a registered
+  /// strategy that does.
+  fn compiles(&self, _source: &Path) -> bool {
+    true
+  }
+
+  /// Whether imports of source files can be normalized, see `Compiler::normalize_imports`.
+  fn supports_smart_imports(&self) -> bool {
+    false
+  }
+
+  /// The version of the compiler, which is part of the compile cache. Compilers that can't
+  /// report a version are identified by their name only.
+  fn version(&self) -> Option<String> {
+    let output = spawn_shell_command(&format!("{} {}", self.executable(), VERSION_OPTION)).ok()?.wait_with_output().ok()?;
+    if output.status.success() {
+      Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+      None
+    }
+  }
+
+  /// Runs the compiler. Diagnostics of the returned output or `CompilerError::CompilationFailed`
+  /// may refer to normalized source files, they're mapped to the original source files afterwards.
+  fn compile(&self, input: &CompilationInput) -> Result<CompilerOutput, CompilerError>;
+
+  /// Writes artifacts of the compiled contracts to `artifacts_dir`. Contracts of `output` are
+  /// keyed by their artifact names, which are the contract names unless several source files
+  /// define contracts of the same name, see `artifact::ArtifactNames`. By default, ABI and
+  /// bytecode are written to `<ArtifactName>.abi` and `<ArtifactName>.bin`, just like
+  /// `solc --abi --bin -o` does.
+  fn collect_artifacts(&self, output: &CompilerOutput, artifacts_dir: &Path) -> Result<(), CompilerError> {
+    if output.contracts.is_empty() {
+      return Ok(());
+    }
+    fs::create_dir_all(artifacts_dir)?;
+    for (name, contract) in output.contracts.values().flat_map(|contracts| contracts.iter()) {
+      let abi = serde_json::to_string(&contract.abi).map_err(|err| CompilerError::Other(err.to_string()))?;
+      fs::write(artifacts_dir.join(name).with_extension(ARTIFACT_EXTENSION_ABI), abi)?;
+      fs::write(artifacts_dir.join(name).with_extension(ARTIFACT_EXTENSION_BINARY), &contract.evm.bytecode.object)?;
+    }
+    Ok(())
+  }
+}
+
+/// The compiler strategies available to `Compiler`. Strategies registered later take
+/// precedence, so that built-in strategies can be replaced.
+#[derive(Clone)]
+pub struct CompilerStrategies {
+  strategies: Vec<Arc<dyn CompilerStrategy>>,
+}
+
+impl CompilerStrategies {
+  pub fn register(&mut self, strategy: Arc<dyn CompilerStrategy>) {
+    self.strategies.push(strategy);
+  }
+
+  /// Returns the strategy for the given compiler name or executable.
+  pub fn find(&self, compiler: &str) -> Option<Arc<dyn CompilerStrategy>> {
+    self.strategies.iter()
+      .rev()
+      .find(|strategy| strategy.name() == compiler || strategy.executable() == compiler)
+      .cloned()
+  }
+
+  /// Returns the strategy that compiles the given source file.
+  pub fn find_for_source(&self, source: &Path) -> Option<Arc<dyn CompilerStrategy>> {
+    self.strategies.iter()
+      .rev()
+      .find(|strategy| strategy.compiles(source))
+      .cloned()
+  }
+}
+
+impl Default for CompilerStrategies {
+  fn default() -> Self {
+    CompilerStrategies {
+      // Strategies are looked up in reverse order, which makes solc the default for Solidity files.
+      strategies: vec![Arc::new(Vyper), Arc::new(SolcJs), Arc::new(Solc)],
+    }
+  }
+}
+
+impl fmt::Debug for CompilerStrategies {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_list().entries(self.strategies.iter().map(|strategy| strategy.name())).finish()
+  }
+}
+
+/// Runs the given command line through the platform's shell, with piped stdin, stdout and stderr.
+pub fn spawn_shell_command(command_line: &str) -> Result<Child, CompilerError> {
+  let (shell, shell_opt) = if cfg!(target_os = "windows") {
+    ("cmd", "/C")
+  } else {
+    ("sh", "-c")
+  };
+
+  info!("Running compiler command: {} {} {}", &shell, &shell_opt, command_line);
+
+  Command::new(shell)
+    .arg(shell_opt)
+    .arg(command_line)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .map_err(|err| {
+      match err.kind() {
+        std::io::ErrorKind::NotFound => CompilerError::ExecutableNotFound(err, shell.to_owned()),
+        _ => CompilerError::Io(err)
+      }
+    })
+}
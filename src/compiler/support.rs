@@ -1,68 +1,455 @@
+use super::diagnostics::{self, CompilerDiagnostic, DiagnosticSeverity};
 use super::error;
+use super::standard_json::{StandardJsonContract, StandardJsonEvm, StandardJsonInput, StandardJsonOutput, STANDARD_JSON_OPTION};
+use super::remappings::Remapping;
+use super::strategy::{spawn_shell_command, CompilationInput, CompilerStrategy};
+use super::versions::SolcRelease;
+use super::{CompilerOutput, ARTIFACT_EXTENSION_ABI, ARTIFACT_EXTENSION_BINARY};
 
-use std::str::FromStr;
-use std::string::ToString;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 const SOLC_COMPILER_BINARY_UNIX: &str = "solc";
 const SOLC_COMPILER_BINARY_WINDOWS: &str = "solc.exe";
 const SOLC_JS_COMPILER_BINARY: &str = "solcjs";
+const VYPER_COMPILER_BINARY: &str = "vyper";
 
-pub enum SupportedCompilers {
-  Solc,
-  SolcJs,
+const SOLIDITY_EXTENSION: &str = "sol";
+const VYPER_EXTENSION: &str = "vy";
+
+const ALLOW_PATHS_OPTION: &str = "--allow-paths";
+const VYPER_FORMAT_OPTION: &str = "-f abi,bytecode,bytecode_runtime";
+
+/// Compiles with solc's standard JSON interface, unless compiler options are specified.
+pub struct Solc;
+
+/// Compiles with solcjs' standard JSON interface, unless compiler options are specified.
+pub struct SolcJs;
+
+/// Compiles Vyper source files one by one, each into a contract named after the file.
+pub struct Vyper;
+
+/// Runs a compiler without built-in support. Such compilers can only be used with compiler
+/// options, which are passed to the compiler followed by the artifacts directory and source files.
+pub struct CommandLineCompiler {
+  executable: String,
 }
 
-impl SupportedCompilers {
-  pub fn executable(&self) -> String {
-    match self {
-      SupportedCompilers::Solc => {
-        if cfg!(target_os = "windows") {
-          SOLC_COMPILER_BINARY_WINDOWS.to_string()
-        } else {
-          SOLC_COMPILER_BINARY_UNIX.to_string()
+impl CompilerStrategy for Solc {
+  fn name(&self) -> &str {
+    SOLC_COMPILER_BINARY_UNIX
+  }
+
+  fn executable(&self) -> String {
+    if cfg!(target_os = "windows") {
+      SOLC_COMPILER_BINARY_WINDOWS.to_string()
+    } else {
+      SOLC_COMPILER_BINARY_UNIX.to_string()
+    }
+  }
+
+  fn default_options(&self) -> Vec<String> {
+    vec![
+      "--abi".to_string(),
+      "--bin".to_string(),
+      "--overwrite".to_string(),
+      "-o".to_string()
+    ]
+  }
+
+  fn supports_smart_imports(&self) -> bool {
+    true
+  }
+
+  fn compiles(&self, source: &Path) -> bool {
+    has_extension(source, SOLIDITY_EXTENSION)
+  }
+
+  fn compile(&self, input: &CompilationInput) -> Result<CompilerOutput, error::CompilerError> {
+    compile_solc(&self.executable(), input)
+  }
+}
+
+/// Compiles with a solc release of the local compiler store, just like `Solc` does.
+impl CompilerStrategy for SolcRelease {
+  fn name(&self) -> &str {
+    SOLC_COMPILER_BINARY_UNIX
+  }
+
+  fn executable(&self) -> String {
+    self.executable.to_string_lossy().to_string()
+  }
+
+  fn default_options(&self) -> Vec<String> {
+    Solc.default_options()
+  }
+
+  fn compiles(&self, source: &Path) -> bool {
+    Solc.compiles(source)
+  }
+
+  fn supports_smart_imports(&self) -> bool {
+    true
+  }
+
+  fn version(&self) -> Option<String> {
+    Some(self.version.to_string())
+  }
+
+  fn compile(&self, input: &CompilationInput) -> Result<CompilerOutput, error::CompilerError> {
+    compile_solc(&self.executable(), input)
+  }
+}
+
+impl CompilerStrategy for SolcJs {
+  fn name(&self) -> &str {
+    SOLC_JS_COMPILER_BINARY
+  }
+
+  fn default_options(&self) -> Vec<String> {
+    vec![
+      "--abi".to_string(),
+      "--bin".to_string(),
+      "-o".to_string()
+    ]
+  }
+
+  fn supports_smart_imports(&self) -> bool {
+    true
+  }
+
+  fn compiles(&self, source: &Path) -> bool {
+    has_extension(source, SOLIDITY_EXTENSION)
+  }
+
+  fn compile(&self, input: &CompilationInput) -> Result<CompilerOutput, error::CompilerError> {
+    if !input.options.is_empty() {
+      return compile_with_options(&self.executable(), input, &[]);
+    }
+    compile_standard_json(&[self.executable(), STANDARD_JSON_OPTION.to_string()], input)
+  }
+}
+
+impl CompilerStrategy for Vyper {
+  fn name(&self) -> &str {
+    VYPER_COMPILER_BINARY
+  }
+
+  fn compiles(&self, source: &Path) -> bool {
+    has_extension(source, VYPER_EXTENSION)
+  }
+
+  fn compile(&self, input: &CompilationInput) -> Result<CompilerOutput, error::CompilerError> {
+    let mut output = CompilerOutput::default();
+    for file in input.input_files {
+      let mut command_line = vec![self.executable(), VYPER_FORMAT_OPTION.to_string()];
+      command_line.extend(input.options.iter().cloned());
+      command_line.push(file.clone());
+
+      let process_output = spawn_shell_command(&command_line.join(" "))?.wait_with_output()?;
+      let stderr = String::from_utf8_lossy(&process_output.stderr).to_string();
+
+      if !process_output.status.success() {
+        let diagnostics = parse_vyper_errors(&stderr, Path::new(file));
+        if diagnostics.is_empty() {
+          return Err(error::CompilerError::Other(stderr));
         }
+        return Err(error::CompilerError::CompilationFailed(diagnostics));
       }
-      SupportedCompilers::SolcJs => SOLC_JS_COMPILER_BINARY.to_string(),
+
+      let name = Path::new(file).file_stem().unwrap().to_string_lossy().to_string();
+      let contract = parse_vyper_output(&String::from_utf8_lossy(&process_output.stdout))?;
+      let mut contracts = BTreeMap::new();
+      contracts.insert(name, contract);
+      output.contracts.insert(file.clone(), contracts);
+      output.stderr.extend(process_output.stderr);
     }
+    Ok(output)
   }
 }
 
-impl FromStr for SupportedCompilers {
-  type Err = error::CompilerError;
-  fn from_str(s: &str) -> Result<Self, Self::Err> {
-    match s {
-      SOLC_COMPILER_BINARY_UNIX => Ok(SupportedCompilers::Solc),
-      SOLC_JS_COMPILER_BINARY => Ok(SupportedCompilers::SolcJs),
-      _ => Err(error::CompilerError::UnsupportedStrategy),
+impl CommandLineCompiler {
+  pub fn new(executable: String) -> CommandLineCompiler {
+    CommandLineCompiler {
+      executable
     }
   }
 }
 
-impl ToString for SupportedCompilers {
-  fn to_string(&self) -> String {
-    match self {
-      SupportedCompilers::Solc => SOLC_COMPILER_BINARY_UNIX.to_string(),
-      SupportedCompilers::SolcJs => SOLC_JS_COMPILER_BINARY.to_string(),
+impl CompilerStrategy for CommandLineCompiler {
+  fn name(&self) -> &str {
+    &self.executable
+  }
+
+  fn compile(&self, input: &CompilationInput) -> Result<CompilerOutput, error::CompilerError> {
+    if input.options.is_empty() {
+      return Err(error::CompilerError::UnsupportedStrategy);
     }
+    compile_with_options(&self.executable, input, &[])
   }
 }
 
-pub fn default_options_from(compiler: SupportedCompilers) -> Vec<String> {
-  match compiler {
-    SupportedCompilers::Solc => {
-      vec![
-        "--abi".to_string(),
-        "--bin".to_string(),
-        "--overwrite".to_string(),
-        "-o".to_string()
-      ]
-    },
-    SupportedCompilers::SolcJs => {
-      vec![
-        "--abi".to_string(),
-        "--bin".to_string(),
-        "-o".to_string()
-      ]
-    },
+fn compile_solc(executable: &str, input: &CompilationInput) -> Result<CompilerOutput, error::CompilerError> {
+  if !input.options.is_empty() {
+    // solc expects remappings as positional arguments before the source files.
+    let remappings = input.remappings.iter().map(Remapping::to_string).collect::<Vec<String>>();
+    return compile_with_options(executable, input, &remappings);
+  }
+  // Allows solc to read imported files that aren't part of the input.
+  let command_line = vec![
+    executable.to_string(),
+    STANDARD_JSON_OPTION.to_string(),
+    ALLOW_PATHS_OPTION.to_string(),
+    input.project_path.to_string_lossy().to_string(),
+  ];
+  compile_standard_json(&command_line, input)
+}
+
+/// Runs the given command line with the standard JSON input for `input` and parses its output.
+pub fn compile_standard_json(command_line: &[String], input: &CompilationInput) -> Result<CompilerOutput, error::CompilerError> {
+  let input_files = input.input_files.iter().map(PathBuf::from).collect::<Vec<PathBuf>>();
+  let remappings = input.remappings.iter().map(Remapping::to_string).collect();
+  let standard_json_input = StandardJsonInput::from_files(&input_files, remappings)?;
+
+  let mut process = spawn_shell_command(&command_line.join(" "))?;
+  let input_json = serde_json::to_string(&standard_json_input).map_err(|err| error::CompilerError::Other(err.to_string()))?;
+  // Dropping stdin after writing the input closes it, so the compiler starts compiling.
+  process.stdin.take().unwrap().write_all(input_json.as_bytes())?;
+  let output = process.wait_with_output()?;
+
+  if !output.status.success() && output.stdout.is_empty() {
+    return Err(error::CompilerError::Other(String::from_utf8_lossy(&output.stderr).to_string()));
+  }
+
+  let standard_json_output = StandardJsonOutput::parse(&output.stdout)?;
+  let diagnostics = standard_json_output.errors.iter()
+    .map(|error| CompilerDiagnostic::from_standard_json(error, &standard_json_input))
+    .collect();
+
+  if standard_json_output.has_errors() {
+    return Err(error::CompilerError::CompilationFailed(diagnostics));
+  }
+
+  Ok(CompilerOutput {
+    contracts: standard_json_output.contracts,
+    diagnostics,
+    ..CompilerOutput::default()
+  })
+}
+
+/// Runs `executable` with the options of `input`, followed by the artifacts directory, the given
+/// arguments and source files. Diagnostics are parsed from the compiler's stderr. Artifacts named
+/// after their source file, like the ones of solcjs, are read into `CompilerOutput::contracts`,
+/// so that they're written under the name of their contract, see `collect_mangled_artifacts`.
+pub fn compile_with_options(executable: &str, input: &CompilationInput, arguments: &[String]) -> Result<CompilerOutput, error::CompilerError> {
+  let mut command_line = vec![executable.to_string()];
+  command_line.extend(input.options.iter().cloned());
+  command_line.push(input.artifacts_dir.to_string_lossy().to_string());
+  command_line.extend(arguments.iter().cloned());
+  command_line.extend(input.input_files.iter().cloned());
+
+  let output = spawn_shell_command(&command_line.join(" "))?.wait_with_output()?;
+  let diagnostics = diagnostics::parse(&String::from_utf8_lossy(&output.stderr));
+
+  if !output.status.success() {
+    if diagnostics.iter().any(CompilerDiagnostic::is_error) {
+      return Err(error::CompilerError::CompilationFailed(diagnostics));
+    }
+    return Err(error::CompilerError::Other(String::from_utf8_lossy(&output.stderr).to_string()));
+  }
+
+  Ok(CompilerOutput {
+    contracts: collect_mangled_artifacts(input)?,
+    diagnostics,
+    stdout: output.stdout,
+    stderr: output.stderr,
+    ..CompilerOutput::default()
+  })
+}
+
+/// Reads and removes artifacts that are named after the source file of their contract. solcjs
+/// writes `<SourcePath>_<ContractName>.abi` and `.bin`, with `/`, `\`, `.` and `:` of the source
+/// path replaced by `_`, e.g. `__path_to_contracts_Token_sol_Token.abi`. Only artifacts of the
+/// input files with both ABI and bytecode are collected. Returns the contracts by input file.
+pub fn collect_mangled_artifacts(input: &CompilationInput) -> Result<BTreeMap<String, BTreeMap<String, StandardJsonContract>>, error::CompilerError> {
+  let mut contracts: BTreeMap<String, BTreeMap<String, StandardJsonContract>> = BTreeMap::new();
+  if !input.artifacts_dir.is_dir() {
+    return Ok(contracts);
+  }
+
+  let prefixes = input.input_files.iter()
+    .map(|file| (file, format!("{}_", mangle_source_path(file))))
+    .collect::<Vec<(&String, String)>>();
+
+  for entry in fs::read_dir(input.artifacts_dir)?.filter_map(Result::ok) {
+    let abi_path = entry.path();
+    if abi_path.extension().and_then(OsStr::to_str) != Some(ARTIFACT_EXTENSION_ABI) {
+      continue;
+    }
+    let bin_path = abi_path.with_extension(ARTIFACT_EXTENSION_BINARY);
+    let stem = abi_path.file_stem().and_then(OsStr::to_str).unwrap_or_default().trim_start_matches('_').to_string();
+    // The longest prefix wins, in case a source path is a prefix of another one.
+    let mangled = prefixes.iter()
+      .filter(|(_file, prefix)| stem.starts_with(prefix.as_str()) && stem.len() > prefix.len())
+      .max_by_key(|(_file, prefix)| prefix.len());
+
+    if let (Some((file, prefix)), true) = (mangled, bin_path.is_file()) {
+      let abi = fs::read_to_string(&abi_path)?;
+      let mut contract = StandardJsonContract {
+        abi: serde_json::from_str(&abi).map_err(|err| error::CompilerError::Other(format!("Couldn't parse ABI {:?}: {}", abi_path, err)))?,
+        ..StandardJsonContract::default()
+      };
+      contract.evm.bytecode.object = fs::read_to_string(&bin_path)?.trim().to_string();
+      contracts.entry(file.to_string()).or_default().insert(stem[prefix.len()..].to_string(), contract);
+      fs::remove_file(&abi_path)?;
+      fs::remove_file(&bin_path)?;
+    }
+  }
+  Ok(contracts)
+}
+
+// Mangles a source path like solcjs does for artifact names. Leading separators, which solcjs
+// mangles differently depending on its version and platform, are omitted.
+fn mangle_source_path(path: &str) -> String {
+  path.replace(&['/', '\\', '.', ':'][..], "_").trim_start_matches('_').to_string()
+}
+
+fn has_extension(source: &Path, extension: &str) -> bool {
+  source.extension().and_then(OsStr::to_str) == Some(extension)
+}
+
+// `vyper -f abi,bytecode,bytecode_runtime` prints the ABI, the bytecode and the runtime
+// bytecode on separate lines.
+fn parse_vyper_output(stdout: &str) -> Result<StandardJsonContract, error::CompilerError> {
+  let mut lines = stdout.lines().map(str::trim).filter(|line| !line.is_empty());
+  let (abi, bytecode, runtime_bytecode) = match (lines.next(), lines.next(), lines.next()) {
+    (Some(abi), Some(bytecode), Some(runtime_bytecode)) => (abi, bytecode, runtime_bytecode),
+    _ => return Err(error::CompilerError::Other(format!("Couldn't parse Vyper output: {}", stdout))),
+  };
+
+  let mut evm = StandardJsonEvm::default();
+  evm.bytecode.object = bytecode.trim_start_matches("0x").to_string();
+  evm.deployed_bytecode.object = runtime_bytecode.trim_start_matches("0x").to_string();
+  Ok(StandardJsonContract {
+    abi: serde_json::from_str(abi).map_err(|err| error::CompilerError::Other(format!("Couldn't parse Vyper ABI: {}", err)))?,
+    evm,
+    ..StandardJsonContract::default()
+  })
+}
+
+// Vyper reports errors as exceptions, e.g. `vyper.exceptions.StructureException: line 3:4 Invalid statement`.
+// Newer versions report the location on the following lines. Columns reported by Vyper start at 0.
+fn parse_vyper_errors(stderr: &str, file: &Path) -> Vec<CompilerDiagnostic> {
+  let header = Regex::new(r"^vyper\.exceptions\.(?P<kind>\w+): (?P<message>.*)$").unwrap();
+  let location = Regex::new(r"line (?P<line>\d+):(?P<column>\d+)\s*").unwrap();
+
+  let mut diagnostics: Vec<CompilerDiagnostic> = vec![];
+  for line in stderr.lines() {
+    if let Some(captures) = header.captures(line.trim()) {
+      let message = &captures["message"];
+      diagnostics.push(CompilerDiagnostic {
+        severity: DiagnosticSeverity::Error,
+        file: Some(file.to_path_buf()),
+        line: None,
+        column: None,
+        message: location.replace(message, "").trim().to_string(),
+        code: Some(captures["kind"].to_string()),
+      });
+    }
+    if let (Some(diagnostic), Some(captures)) = (diagnostics.last_mut(), location.captures(line)) {
+      if diagnostic.line.is_none() {
+        diagnostic.line = captures["line"].parse().ok();
+        diagnostic.column = captures["column"].parse::<usize>().ok().map(|column| column + 1);
+      }
+    }
+  }
+  diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+
+  extern crate tempfile;
+
+  use std::fs;
+  use std::path::{Path, PathBuf};
+  use tempfile::tempdir;
+  use super::{collect_mangled_artifacts, parse_vyper_errors, parse_vyper_output, Vyper};
+  use crate::compiler::diagnostics::SourceMapping;
+  use crate::compiler::strategy::{CompilationInput, CompilerStrategies, CompilerStrategy};
+
+  #[test]
+  fn it_should_collect_artifacts_named_after_source_files() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = tempdir()?;
+    let artifacts_dir = tmp_dir.path().join("artifacts");
+    fs::create_dir_all(&artifacts_dir)?;
+    fs::write(artifacts_dir.join("__path_to_contracts_Token_sol_Token.abi"), "[]")?;
+    fs::write(artifacts_dir.join("__path_to_contracts_Token_sol_Token.bin"), "6080\n")?;
+    fs::write(artifacts_dir.join("_path_to_contracts_Token_sol_Token_Sale.abi"), "[]")?;
+    fs::write(artifacts_dir.join("_path_to_contracts_Token_sol_Token_Sale.bin"), "6060")?;
+    fs::write(artifacts_dir.join("Ownable.abi"), "[]")?;
+    fs::write(artifacts_dir.join("Ownable.bin"), "6040")?;
+
+    let input_files = vec!["/path/to/contracts/Token.sol".to_string()];
+    let input = CompilationInput {
+      project_path: tmp_dir.path(),
+      artifacts_dir: &artifacts_dir,
+      input_files: &input_files,
+      source_mapping: &SourceMapping::new(),
+      options: &[],
+      remappings: &[],
+    };
+    let contracts = collect_mangled_artifacts(&input)?;
+
+    let token_contracts = &contracts["/path/to/contracts/Token.sol"];
+    assert_eq!(token_contracts.keys().collect::<Vec<&String>>(), vec!["Token", "Token_Sale"]);
+    assert_eq!(token_contracts["Token"].evm.bytecode.object, "6080");
+    assert!(!artifacts_dir.join("__path_to_contracts_Token_sol_Token.abi").exists());
+    assert!(artifacts_dir.join("Ownable.abi").exists());
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_parse_vyper_output() -> Result<(), Box<dyn std::error::Error>> {
+    let stdout = "[{\"name\": \"get\", \"outputs\": [], \"inputs\": [], \"type\": \"function\"}]\n0x6100f456600436\n0x600436\n";
+    let contract = parse_vyper_output(stdout)?;
+
+    assert_eq!(contract.abi[0]["name"], "get");
+    assert_eq!(contract.evm.bytecode.object, "6100f456600436");
+    assert_eq!(contract.evm.deployed_bytecode.object, "600436");
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_parse_vyper_errors() {
+    let file = Path::new("contracts/Vault.vy");
+    let stderr = "Error compiling: contracts/Vault.vy\nvyper.exceptions.StructureException: line 3:4 Invalid top-level statement\n";
+    let diagnostics = parse_vyper_errors(stderr, file);
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].code, Some("StructureException".to_string()));
+    assert_eq!(diagnostics[0].message, "Invalid top-level statement");
+    assert_eq!((diagnostics[0].line, diagnostics[0].column), (Some(3), Some(5)));
+    assert_eq!(diagnostics[0].file, Some(PathBuf::from("contracts/Vault.vy")));
+
+    let stderr = "vyper.exceptions.InvalidType: Expected uint256\n  contract \"contracts/Vault.vy:7\", function \"get\", line 7:11 \n";
+    let diagnostics = parse_vyper_errors(stderr, file);
+    assert_eq!(diagnostics[0].message, "Expected uint256");
+    assert_eq!((diagnostics[0].line, diagnostics[0].column), (Some(7), Some(12)));
+  }
+
+  #[test]
+  fn it_should_find_strategies_by_source_file() {
+    let strategies = CompilerStrategies::default();
+
+    assert_eq!(strategies.find_for_source(Path::new("contracts/Token.sol")).unwrap().name(), "solc");
+    assert_eq!(strategies.find_for_source(Path::new("contracts/Vault.vy")).unwrap().name(), Vyper.name());
+    assert!(strategies.find_for_source(Path::new("contracts/Token.txt")).is_none());
   }
 }
@@ -1,10 +1,11 @@
 use crate::utils as lib_utils;
 use lib_utils::adjust_canonicalization;
-use regex::Regex;
+use std::ffi::OsStr;
 use std::path::{PathBuf, Path, Component};
 use super::error;
+use super::remappings::{self, Remapping};
 
-const NODE_MODULES_DIR: &str = "node_modules";
+pub const NODE_MODULES_DIR: &str = "node_modules";
 pub const INTERNAL_SOURCE_DIR: &str = "contracts";
 
 pub fn strip_absolute_prefix(path: &Path) -> PathBuf {
@@ -20,57 +21,69 @@ pub enum ImportType {
 
 type ResolvedImport = (ImportType, PathBuf, PathBuf);
 
-pub fn extract_imports(contents: &mut str) -> Vec<String> {
-  let re = Regex::new(r#"import[\s]*(['"])(.*)(['"]);"#).unwrap();
-  re.captures_iter(&contents).map(|x| x[2].to_string()).collect()
+pub fn resolve_import(import: &str, file_path: &Path, project_path: &PathBuf, destination_path: &PathBuf, remappings: &[Remapping]) -> Result<ResolvedImport, error::CompilerError> {
+  let (import_type, resolved_path) = resolve_import_path(import, file_path, project_path, remappings)?;
+  let normalized_path = match import_type {
+    ImportType::External => destination_path.join(strip_absolute_prefix(&resolved_path)),
+    _ => get_destination_path(&resolved_path, project_path, destination_path),
+  };
+  Ok((import_type, resolved_path, normalized_path))
 }
 
-pub fn resolve_import(import: &str, file_path: &Path, project_path: &PathBuf, destination_path: &PathBuf) -> Result<ResolvedImport, error::CompilerError> {
+/// Resolves an import of a file in the directory `file_path` to the imported file, like the
+/// compiler does: through remappings, from `node_modules` of the directory and its ancestors,
+/// relative to the directory or, for imports like `contracts/Token.sol`, to the project.
+pub fn resolve_import_path(import: &str, file_path: &Path, project_path: &Path, remappings: &[Remapping]) -> Result<(ImportType, PathBuf), error::CompilerError> {
+  let import = remappings::remap(remappings, import, file_path).unwrap_or_else(|| import.to_string());
   let mut import = PathBuf::from(&import).components().collect::<PathBuf>();
 
-  if is_node_module_import(&import, file_path, &project_path) {
-    let resolved_path = if import.starts_with("./") || import.starts_with("..") {
-      adjust_canonicalization(file_path.join(&import).canonicalize().unwrap())
-    } else {
-      project_path.join(NODE_MODULES_DIR).join(&import)
-    };
-    let normalized_path = destination_path.join(resolved_path.strip_prefix(project_path).unwrap());
-    Ok((ImportType::Node, resolved_path, normalized_path))
-  } else if is_internal_import(&import, file_path, &project_path) {
+  if let Some(resolved_path) = resolve_node_module_import(&import, file_path) {
+    Ok((ImportType::Node, resolved_path))
+  } else if is_internal_import(&import, file_path, project_path) || is_project_import(&import, project_path) {
     if !import.is_absolute() {
-      import = file_path.join(import);
+      import = if file_path.join(&import).exists() { file_path.join(import) } else { project_path.join(import) };
     }
-    let resolved_path = adjust_canonicalization(import.canonicalize().unwrap());
-    let normalized_path = destination_path.join(resolved_path.strip_prefix(project_path.join(INTERNAL_SOURCE_DIR)).unwrap());
-    Ok((ImportType::Internal, resolved_path, normalized_path))
+    let canonicalized_import = import.canonicalize().map_err(|_| error::CompilerError::ImportError(import.clone()))?;
+    Ok((ImportType::Internal, adjust_canonicalization(canonicalized_import)))
   } else {
     let canonicalized_import = import.canonicalize().map_err(|_| error::CompilerError::ImportError(file_path.join(import)))?;
-    let resolved_path = adjust_canonicalization(canonicalized_import);
-    let normalized_path = destination_path.join(strip_absolute_prefix(&resolved_path));
-    Ok((ImportType::External, resolved_path, normalized_path))
+    Ok((ImportType::External, adjust_canonicalization(canonicalized_import)))
   }
 }
 
-fn is_node_module_import<T: AsRef<Path>>(path: T, parent_path: &Path, project_path: &PathBuf) -> bool {
-  if path.as_ref().is_absolute() {
-    info!("Found absolute import: {:?}", &path.as_ref());
-    path.as_ref().starts_with(project_path.join(NODE_MODULES_DIR)) && path.as_ref().exists()
-  } else if path.as_ref().starts_with(".") || path.as_ref().starts_with("..") {
-    info!("Found relative import: {:?}", &path.as_ref());
-    match parent_path.join(path).canonicalize() {
-      Ok(p) => p.starts_with(project_path.join(NODE_MODULES_DIR)),
-      Err(_) => false
+/// Looks up a package import like Node does, in the `node_modules` directory of the importing
+/// file's directory and each of its ancestors. This finds packages hoisted to the root of a
+/// workspace as well as nested ones. Scoped packages, e.g. `@scope/package/...`, are nested
+/// directories within `node_modules`.
+pub fn find_node_module(import: &Path, parent_path: &Path) -> Option<PathBuf> {
+  parent_path.ancestors()
+    // Like Node, `node_modules/node_modules` isn't searched.
+    .filter(|directory| directory.file_name() != Some(OsStr::new(NODE_MODULES_DIR)))
+    .map(|directory| directory.join(NODE_MODULES_DIR).join(import))
+    .find(|candidate| candidate.is_file())
+}
+
+// Resolves imports of files within `node_modules` directories, returns `None` for other imports.
+fn resolve_node_module_import(path: &Path, parent_path: &Path) -> Option<PathBuf> {
+  if path.is_absolute() || path.starts_with(".") || path.starts_with("..") {
+    info!("Found absolute or relative import: {:?}", &path);
+    let resolved_path = adjust_canonicalization(parent_path.join(path).canonicalize().ok()?);
+    if resolved_path.components().any(|component| component.as_os_str() == NODE_MODULES_DIR) {
+      Some(resolved_path)
+    } else {
+      None
     }
-  } else if !path.as_ref().starts_with(NODE_MODULES_DIR) {
-    info!("Found implicit node_module import: {:?}", &path.as_ref());
-    project_path.join(NODE_MODULES_DIR).join(&path).exists()
   } else {
-    info!("Found explicit node_module import: {:?}", &path.as_ref());
-    project_path.join(&path).exists()
+    info!("Found node_module import: {:?}", &path);
+    // Explicit imports, e.g. `node_modules/package/...`, are looked up just like implicit ones.
+    let package_path = path.strip_prefix(NODE_MODULES_DIR).unwrap_or(path);
+    find_node_module(package_path, parent_path)
+      .and_then(|module_path| module_path.canonicalize().ok())
+      .map(adjust_canonicalization)
   }
 }
 
-fn is_internal_import<T: AsRef<Path>>(path: T, parent_path: &Path, project_path: &PathBuf) -> bool {
+fn is_internal_import<T: AsRef<Path>>(path: T, parent_path: &Path, project_path: &Path) -> bool {
   if !path.as_ref().is_absolute() {
     match parent_path.join(&path).canonicalize() {
       Ok(p) => adjust_canonicalization(p).starts_with(project_path),
@@ -81,12 +94,18 @@ fn is_internal_import<T: AsRef<Path>>(path: T, parent_path: &Path, project_path:
   }
 }
 
+// Whether the import is a path relative to the project that isn't a relative import, e.g. `contracts/Token.sol`.
+fn is_project_import(path: &Path, project_path: &Path) -> bool {
+  !path.is_absolute() && !path.starts_with(".") && !path.starts_with("..") && project_path.join(path).is_file()
+}
+
 pub fn get_destination_path(original_path: &PathBuf, project_path: &PathBuf, destination_root: &PathBuf) -> PathBuf {
   if let Ok(path) = original_path.strip_prefix(&project_path) {
     if path.starts_with(NODE_MODULES_DIR) {
       destination_root.join(path)
     } else {
-      destination_root.join(path.strip_prefix(INTERNAL_SOURCE_DIR).unwrap())
+      // Files outside of the contracts directory, e.g. vendored libraries, keep their project path.
+      destination_root.join(path.strip_prefix(INTERNAL_SOURCE_DIR).unwrap_or(path))
     }
   } else {
     destination_root.join(strip_absolute_prefix(&original_path))
@@ -130,7 +149,7 @@ mod tests {
       fs::create_dir_all(absolute_path.parent().unwrap())?;
       fs::File::create(&absolute_path)?;
 
-      let resolved_import = resolve_import(&import_path.to_str().unwrap(), &project_path, &project_path, &destination_path)?;
+      let resolved_import = resolve_import(&import_path.to_str().unwrap(), &project_path, &project_path, &destination_path, &[])?;
 
       assert_eq!(resolved_import.0, ImportType::Internal);
       assert_eq!(resolved_import.1, project_path.join(&import_path));
@@ -140,6 +159,25 @@ mod tests {
       Ok(())
     }
 
+    #[test]
+    fn it_should_resolve_imports_relative_to_the_project() -> Result<(), Box<dyn std::error::Error>> {
+      let (tmp_dir, project_path) = create_test_project()?;
+      let file_path = project_path.join("contracts").join("tokens");
+      let destination_path = project_path.join(".vibranium").join("contracts");
+      fs::create_dir_all(&file_path)?;
+      fs::create_dir_all(project_path.join("lib"))?;
+      fs::File::create(project_path.join("lib").join("Math.sol"))?;
+
+      let resolved_import = resolve_import("lib/Math.sol", &file_path, &project_path, &destination_path, &[])?;
+
+      assert_eq!(resolved_import.0, ImportType::Internal);
+      assert_eq!(resolved_import.1, project_path.join("lib").join("Math.sol"));
+      assert_eq!(resolved_import.2, destination_path.join("lib").join("Math.sol"));
+
+      tmp_dir.close()?;
+      Ok(())
+    }
+
     #[test]
     fn it_should_resolve_node_module_imports() -> Result<(), Box<std::error::Error>>  {
       let (tmp_dir, project_path) = create_test_project()?;
@@ -151,7 +189,7 @@ mod tests {
       fs::create_dir_all(absolute_path.parent().unwrap())?;
       fs::File::create(&absolute_path)?;
 
-      let resolved_import = resolve_import(&import_path.to_str().unwrap(), &absolute_node_modules_path, &project_path, &destination_path)?;
+      let resolved_import = resolve_import(&import_path.to_str().unwrap(), &absolute_node_modules_path, &project_path, &destination_path, &[])?;
 
       assert_eq!(resolved_import.0, ImportType::Node);
       assert_eq!(resolved_import.1, absolute_node_modules_path.join(&import_path));
@@ -172,7 +210,7 @@ mod tests {
       fs::create_dir_all(absolute_external_path.parent().unwrap())?;
       fs::File::create(&absolute_external_path)?;
 
-      let resolved_import = resolve_import(&absolute_external_path.to_str().unwrap(), &external_project_path, &project_path, &destination_path)?;
+      let resolved_import = resolve_import(&absolute_external_path.to_str().unwrap(), &external_project_path, &project_path, &destination_path, &[])?;
 
       assert_eq!(resolved_import.0, ImportType::External);
       assert_eq!(resolved_import.1, absolute_external_path);
@@ -182,17 +220,63 @@ mod tests {
       tmp_dir2.close()?;
       Ok(())
     }
+    #[test]
+    fn it_should_resolve_node_module_imports_hoisted_to_parent_directories() -> Result<(), Box<dyn std::error::Error>> {
+      let (tmp_dir, project_path) = create_test_project()?;
+      let import_path = PathBuf::from("@scope").join("package").join("some.sol");
+      let workspace_path = project_path.parent().unwrap().to_path_buf();
+      let absolute_path = workspace_path.join("node_modules").join(&import_path);
+      let destination_path = project_path.join(".vibranium").join("contracts");
+
+      fs::create_dir_all(absolute_path.parent().unwrap())?;
+      fs::File::create(&absolute_path)?;
+      fs::create_dir_all(project_path.join("contracts"))?;
+
+      let resolved_import = resolve_import(import_path.to_str().unwrap(), &project_path.join("contracts"), &project_path, &destination_path, &[])?;
+
+      assert_eq!(resolved_import.0, ImportType::Node);
+      assert_eq!(resolved_import.1, absolute_path);
+      assert_eq!(resolved_import.2, destination_path.join(strip_absolute_prefix(&absolute_path)));
+
+      tmp_dir.close()?;
+      Ok(())
+    }
+
+    #[test]
+    fn it_should_prefer_nested_node_modules() -> Result<(), Box<dyn std::error::Error>> {
+      let (tmp_dir, project_path) = create_test_project()?;
+      let package_path = project_path.join("node_modules").join("a");
+      let nested_path = package_path.join("node_modules").join("b").join("B.sol");
+      let hoisted_path = project_path.join("node_modules").join("b").join("B.sol");
+      let destination_path = project_path.join(".vibranium").join("contracts");
+
+      for path in &[&nested_path, &hoisted_path] {
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::File::create(path)?;
+      }
+      fs::create_dir_all(package_path.join("contracts"))?;
+
+      let resolved_import = resolve_import("b/B.sol", &package_path.join("contracts"), &project_path, &destination_path, &[])?;
+      assert_eq!(resolved_import.1, nested_path);
+      assert_eq!(resolved_import.2, destination_path.join("node_modules").join("a").join("node_modules").join("b").join("B.sol"));
+
+      let resolved_import = resolve_import("b/B.sol", &project_path.join("contracts"), &project_path, &destination_path, &[])?;
+      assert_eq!(resolved_import.1, hoisted_path);
+
+      tmp_dir.close()?;
+      Ok(())
+    }
   }
 
-  mod is_node_module_import {
+  mod resolve_node_module_import {
 
     use std::fs;
     use std::path::PathBuf;
     use super::create_test_project;
-    use super::super::is_node_module_import;
+    use super::super::{find_node_module, resolve_node_module_import};
 
     #[test]
-    fn it_should_be_true_with_implicit_import_syntax() -> Result<(), Box<std::error::Error>> {
+    fn it_should_resolve_implicit_import_syntax() -> Result<(), Box<std::error::Error>> {
       let (tmp_dir, project_path) = create_test_project()?;
 
       let import_path = PathBuf::from("@aragon").join("something").join("foo.sol");
@@ -202,14 +286,15 @@ mod tests {
       fs::create_dir_all(&absolute_node_module_path.parent().unwrap())?;
       fs::File::create(&absolute_node_module_path)?;
 
-      assert_eq!(is_node_module_import(&import_path, &project_path, &project_path), true);
+      assert_eq!(find_node_module(&import_path, &project_path), Some(absolute_node_module_path.clone()));
+      assert_eq!(resolve_node_module_import(&import_path, &project_path), Some(absolute_node_module_path));
 
       tmp_dir.close()?;
       Ok(())
     }
 
     #[test]
-    fn it_should_be_true_with_explicit_import_syntax() -> Result<(), Box<std::error::Error>> {
+    fn it_should_resolve_explicit_import_syntax() -> Result<(), Box<std::error::Error>> {
       let (tmp_dir, project_path) = create_test_project()?;
       let import_path = PathBuf::from("@aragon").join("something").join("foo.sol");
       let relative_node_module_path = PathBuf::from("node_modules").join(&import_path);
@@ -218,7 +303,22 @@ mod tests {
       fs::create_dir_all(&absolute_node_module_path.parent().unwrap())?;
       fs::File::create(&absolute_node_module_path)?;
 
-      assert_eq!(is_node_module_import(&relative_node_module_path, &project_path, &project_path), true);
+      assert_eq!(resolve_node_module_import(&relative_node_module_path, &project_path), Some(absolute_node_module_path));
+
+      tmp_dir.close()?;
+      Ok(())
+    }
+
+    #[test]
+    fn it_should_not_resolve_imports_outside_of_node_modules() -> Result<(), Box<dyn std::error::Error>> {
+      let (tmp_dir, project_path) = create_test_project()?;
+      let import_path = PathBuf::from("contracts").join("foo.sol");
+
+      fs::create_dir_all(project_path.join("contracts"))?;
+      fs::File::create(project_path.join(&import_path))?;
+
+      assert_eq!(resolve_node_module_import(&PathBuf::from(".").join(&import_path), &project_path), None);
+      assert_eq!(resolve_node_module_import(&PathBuf::from("foo.sol"), &project_path.join("contracts")), None);
 
       tmp_dir.close()?;
       Ok(())
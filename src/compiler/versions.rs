@@ -0,0 +1,161 @@
+use super::strategy::CompilerStrategy;
+use super::support::Solc;
+use crate::project_generator::VIBRANIUM_PROJECT_DIRECTORY;
+use crate::utils as lib_utils;
+
+use regex::Regex;
+use semver::{Version, VersionReq};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const COMPILERS_DIRECTORY: &str = "compilers";
+
+/// A version requirement as used by `pragma solidity` and `compiler.version`,
+/// e.g. `^0.5.0` or `>=0.4.22 <0.6.0 || 0.6.1`. Versions without operator match exactly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionPragma {
+  pragma: String,
+  alternatives: Vec<VersionReq>,
+}
+
+impl VersionPragma {
+  pub fn parse(pragma: &str) -> Result<VersionPragma, String> {
+    let comparator = Regex::new(r"(\^|~|>=|<=|>|<|=)?\s*([0-9xX*][0-9A-Za-z.*+-]*)").unwrap();
+    let alternatives = pragma.split("||")
+      .map(|alternative| {
+        let comparators = comparator.captures_iter(alternative)
+          .map(|captures| format!("{}{}", captures.get(1).map_or("=", |operator| operator.as_str()), &captures[2]))
+          .collect::<Vec<String>>();
+        if comparators.is_empty() {
+          return Err(format!("missing version in {:?}", alternative.trim()));
+        }
+        VersionReq::parse(&comparators.join(", ")).map_err(|err| err.to_string())
+      })
+      .collect::<Result<Vec<VersionReq>, String>>()?;
+    Ok(VersionPragma { pragma: pragma.trim().to_string(), alternatives })
+  }
+
+  pub fn matches(&self, version: &Version) -> bool {
+    self.alternatives.iter().any(|requirement| requirement.matches(version))
+  }
+}
+
+impl fmt::Display for VersionPragma {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.pragma)
+  }
+}
+
+/// A solc release installed in the local compiler store.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolcRelease {
+  pub version: Version,
+  pub executable: PathBuf,
+}
+
+/// The solc releases installed in `~/.vibranium/compilers/<version>/solc`.
+#[derive(Debug, Default)]
+pub struct SolcInstallations {
+  releases: Vec<SolcRelease>,
+}
+
+impl SolcInstallations {
+  /// Reads the releases installed in the given directory. Directories that aren't named
+  /// after a version or don't contain a solc executable are ignored.
+  pub fn read(compilers_dir: &Path) -> SolcInstallations {
+    let mut releases = fs::read_dir(compilers_dir)
+      .map(|entries| {
+        entries.filter_map(Result::ok)
+          .filter_map(|entry| {
+            let version = Version::parse(entry.file_name().to_string_lossy().trim_start_matches('v')).ok()?;
+            let executable = entry.path().join(Solc.executable());
+            if executable.is_file() {
+              Some(SolcRelease { version, executable })
+            } else {
+              None
+            }
+          })
+          .collect::<Vec<SolcRelease>>()
+      })
+      .unwrap_or_default();
+    releases.sort_by(|a, b| a.version.cmp(&b.version));
+    SolcInstallations { releases }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.releases.is_empty()
+  }
+
+  /// Returns the latest installed release that satisfies all of the given pragmas.
+  pub fn resolve(&self, pragmas: &[VersionPragma]) -> Option<&SolcRelease> {
+    self.releases.iter()
+      .rev()
+      .find(|release| pragmas.iter().all(|pragma| pragma.matches(&release.version)))
+  }
+}
+
+/// The directory of the local compiler store, `~/.vibranium/compilers`.
+pub fn compilers_dir() -> Option<PathBuf> {
+  lib_utils::home_dir().map(|home| home.join(VIBRANIUM_PROJECT_DIRECTORY).join(COMPILERS_DIRECTORY))
+}
+
+#[cfg(test)]
+mod tests {
+
+  extern crate tempfile;
+
+  use semver::Version;
+  use std::fs;
+  use tempfile::tempdir;
+  use super::{SolcInstallations, VersionPragma};
+  use crate::compiler::strategy::CompilerStrategy;
+  use crate::compiler::support::Solc;
+
+  fn version(version: &str) -> Version {
+    Version::parse(version).unwrap()
+  }
+
+  #[test]
+  fn it_should_match_solidity_version_pragmas() -> Result<(), String> {
+    let caret = VersionPragma::parse("^0.5.0")?;
+    assert!(caret.matches(&version("0.5.8")));
+    assert!(!caret.matches(&version("0.6.0")));
+
+    let exact = VersionPragma::parse("0.4.24")?;
+    assert!(exact.matches(&version("0.4.24")));
+    assert!(!exact.matches(&version("0.4.25")));
+
+    let range = VersionPragma::parse(">= 0.4.22 <0.6.0 || ^0.7.0")?;
+    assert!(range.matches(&version("0.4.22")));
+    assert!(range.matches(&version("0.7.1")));
+    assert!(!range.matches(&version("0.6.0")));
+
+    assert!(VersionPragma::parse("experimental").is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_resolve_latest_installed_release_satisfying_all_pragmas() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = tempdir()?;
+    for release in &["0.4.24", "0.5.1", "0.5.8", "v0.6.0"] {
+      fs::create_dir_all(tmp_dir.path().join(release))?;
+      fs::write(tmp_dir.path().join(release).join(Solc.executable()), "")?;
+    }
+    fs::create_dir_all(tmp_dir.path().join("0.5.9"))?;
+
+    let installations = SolcInstallations::read(tmp_dir.path());
+    let resolve = |pragmas: &[&str]| {
+      let pragmas = pragmas.iter().map(|pragma| VersionPragma::parse(pragma).unwrap()).collect::<Vec<VersionPragma>>();
+      installations.resolve(&pragmas).map(|release| release.version.to_string())
+    };
+
+    assert_eq!(resolve(&[]), Some("0.6.0".to_string()));
+    assert_eq!(resolve(&["^0.5.0"]), Some("0.5.8".to_string()));
+    assert_eq!(resolve(&["^0.5.0", "<0.5.8"]), Some("0.5.1".to_string()));
+    assert_eq!(resolve(&["^0.4.0", "^0.5.0"]), None);
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+}
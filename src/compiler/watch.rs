@@ -0,0 +1,88 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+const DEFAULT_POLL_INTERVAL_MS: u64 = 500;
+const DEFAULT_DEBOUNCE_MS: u64 = 300;
+
+/// Configures how `Compiler::watch` waits for changes of watched files.
+#[derive(Debug, Clone)]
+pub struct WatchConfig {
+  /// How often watched files are checked for changes.
+  pub poll_interval: Duration,
+  /// How long watched files have to stay unchanged before recompiling, so that a burst
+  /// of saves causes a single compilation.
+  pub debounce: Duration,
+}
+
+impl Default for WatchConfig {
+  fn default() -> Self {
+    WatchConfig {
+      poll_interval: Duration::from_millis(DEFAULT_POLL_INTERVAL_MS),
+      debounce: Duration::from_millis(DEFAULT_DEBOUNCE_MS),
+    }
+  }
+}
+
+/// Modification times and sizes of watched files. Missing files are part of the snapshot
+/// as well, so that deleting and recreating a file is a change.
+#[derive(Debug, Default, PartialEq)]
+pub struct FileSnapshot {
+  files: BTreeMap<PathBuf, Option<(SystemTime, u64)>>,
+}
+
+impl FileSnapshot {
+  pub fn new(files: BTreeSet<PathBuf>) -> FileSnapshot {
+    FileSnapshot {
+      files: files.into_iter()
+        .map(|file| {
+          let state = fs::metadata(&file).ok()
+            .and_then(|metadata| metadata.modified().ok().map(|modified| (modified, metadata.len())));
+          (file, state)
+        })
+        .collect(),
+    }
+  }
+
+  pub fn files(&self) -> BTreeSet<PathBuf> {
+    self.files.keys().cloned().collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+
+  extern crate tempfile;
+
+  use std::collections::BTreeSet;
+  use std::fs;
+  use tempfile::tempdir;
+  use super::FileSnapshot;
+
+  #[test]
+  fn it_should_detect_changed_created_and_deleted_files() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = tempdir()?;
+    let token = tmp_dir.path().join("Token.sol");
+    let ownable = tmp_dir.path().join("Ownable.sol");
+    fs::write(&token, "contract Token {}")?;
+    let files = vec![token.clone(), ownable.clone()].into_iter().collect::<BTreeSet<_>>();
+
+    let snapshot = FileSnapshot::new(files.clone());
+    assert_eq!(snapshot, FileSnapshot::new(files.clone()));
+
+    fs::write(&token, "contract Token { uint256 supply; }")?;
+    let changed = FileSnapshot::new(files.clone());
+    assert_ne!(snapshot, changed);
+
+    fs::write(&ownable, "contract Ownable {}")?;
+    let created = FileSnapshot::new(files.clone());
+    assert_ne!(changed, created);
+
+    fs::remove_file(&ownable)?;
+    assert_eq!(changed, FileSnapshot::new(files));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+}
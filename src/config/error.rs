@@ -2,16 +2,31 @@ use std::error::Error;
 use std::convert::From;
 use std::fmt;
 use std::io;
+use std::path::PathBuf;
 use toml;
 use toml_query;
+use toml_edit;
+
+use super::VIBRANIUM_CONFIG_FILE;
+use super::migrations::CONFIG_VERSION;
+use super::validation::ConfigDiagnostic;
 
 #[derive(Debug)]
 pub enum ConfigError {
   Serialization(toml::ser::Error),
   Deserialization(toml::de::Error),
   Query(toml_query::error::Error),
-  Deletion(toml_query::error::Error),
+  Parse(toml_edit::TomlError),
+  Deletion(String),
   Io(io::Error),
+  UnknownEnvironment(String),
+  UnknownOption(String),
+  InvalidValue(String, String, String),
+  UndefinedVariable(String),
+  Invalid(Vec<ConfigDiagnostic>),
+  Extends(PathBuf, String),
+  CyclicExtends(Vec<PathBuf>),
+  UnsupportedVersion(i64),
   Other(String),
 }
 
@@ -21,8 +36,17 @@ impl Error for ConfigError {
       ConfigError::Serialization(error) => Some(error),
       ConfigError::Deserialization(error) => Some(error),
       ConfigError::Query(_error) => None,
-      ConfigError::Deletion(_error) => None,
+      ConfigError::Parse(error) => Some(error),
+      ConfigError::Deletion(_option) => None,
       ConfigError::Io(error) => Some(error),
+      ConfigError::UnknownEnvironment(_name) => None,
+      ConfigError::UnknownOption(_option) => None,
+      ConfigError::InvalidValue(_option, _expected, _value) => None,
+      ConfigError::UndefinedVariable(_name) => None,
+      ConfigError::Invalid(_diagnostics) => None,
+      ConfigError::Extends(_path, _message) => None,
+      ConfigError::CyclicExtends(_chain) => None,
+      ConfigError::UnsupportedVersion(_version) => None,
       ConfigError::Other(_message) => None,
     }
   }
@@ -34,8 +58,23 @@ impl fmt::Display for ConfigError {
       ConfigError::Serialization(error) => write!(f, "Couldn't serialize vibranium config: {}", error),
       ConfigError::Deserialization(error) => write!(f, "Couldn't deserialize vibranium config: {}", error),
       ConfigError::Query(error) => write!(f, "Couldn't query configuration: {}", error),
-      ConfigError::Deletion(error) => write!(f, "{}", error),
+      ConfigError::Parse(error) => write!(f, "Couldn't parse vibranium config: {}", error),
+      ConfigError::Deletion(option) => write!(f, "Couldn't delete configuration option '{}' because it isn't empty", option),
       ConfigError::Io(error) => write!(f, "Couldn't access configuration file: {}", error),
+      ConfigError::UnknownEnvironment(name) => write!(f, "Couldn't find configuration for environment '{}'", name),
+      ConfigError::UnknownOption(option) => write!(f, "Couldn't set unknown configuration option '{}'", option),
+      ConfigError::InvalidValue(option, expected, value) => write!(f, "Couldn't set configuration option '{}': expected {} but got {}", option, expected, value),
+      ConfigError::UndefinedVariable(name) => write!(f, "Couldn't interpolate vibranium config: environment variable '{}' is not set and has no default", name),
+      ConfigError::Invalid(diagnostics) => {
+        write!(f, "Found {} problem(s) in {}:", diagnostics.len(), VIBRANIUM_CONFIG_FILE)?;
+        diagnostics.iter().try_for_each(|diagnostic| write!(f, "\n  {}", diagnostic))
+      },
+      ConfigError::Extends(path, message) => write!(f, "Couldn't read extended vibranium config {:?}: {}", path, message),
+      ConfigError::CyclicExtends(chain) => {
+        let chain = chain.iter().map(|path| format!("{:?}", path)).collect::<Vec<String>>();
+        write!(f, "Couldn't read vibranium config due to cyclic `extends`: {}", chain.join(" -> "))
+      },
+      ConfigError::UnsupportedVersion(version) => write!(f, "Couldn't read vibranium config of version {}. The latest supported version is {}, please upgrade Vibranium", version, CONFIG_VERSION),
       ConfigError::Other(message) => write!(f, "{}", message),
     }
   }
@@ -0,0 +1,236 @@
+use super::error::ConfigError;
+use super::migrations;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml;
+
+pub const EXTENDS_KEY: &str = "extends";
+
+/// Resolves the `extends` key of the configuration document read from `path`.
+/// The extended files are resolved recursively, relative to the file that extends
+/// them, and the document is merged on top of them. Tables are merged deeply,
+/// while arrays and all other values of the extending document replace the
+/// extended ones.
+pub fn resolve(document: toml::Value, path: &Path) -> Result<toml::Value, ConfigError> {
+  resolve_chain(document, path, &mut vec![])
+}
+
+/// Returns the fully resolved configuration the document read from `path` extends,
+/// if any.
+pub fn resolve_base(document: &toml::Value, path: &Path) -> Result<Option<toml::Value>, ConfigError> {
+  let mut chain = vec![canonical_path(path)];
+  match base_path(document, path)? {
+    Some(base_path) => read(&base_path).and_then(|base| resolve_chain(base, &base_path, &mut chain)).map(Some),
+    None => Ok(None),
+  }
+}
+
+/// Returns the paths of the files the document read from `path` extends, directly or
+/// transitively. Files that can't be read end the chain, but are part of it.
+pub fn base_paths(document: &toml::Value, path: &Path) -> Vec<PathBuf> {
+  let mut chain = vec![canonical_path(path)];
+  let mut paths = vec![];
+  let mut next = base_path(document, path).ok().and_then(|next| next);
+
+  while let Some(path) = next.filter(|path| !chain.contains(&canonical_path(path))) {
+    chain.push(canonical_path(&path));
+    next = read(&path).ok().and_then(|base| base_path(&base, &path).ok().and_then(|next| next));
+    paths.push(path);
+  }
+  paths
+}
+
+fn resolve_chain(document: toml::Value, path: &Path, chain: &mut Vec<PathBuf>) -> Result<toml::Value, ConfigError> {
+  let path_id = canonical_path(path);
+  if chain.contains(&path_id) {
+    chain.push(path_id);
+    return Err(ConfigError::CyclicExtends(chain.clone()));
+  }
+  chain.push(path_id);
+
+  match base_path(&document, path)? {
+    Some(base_path) => {
+      let base = resolve_chain(read(&base_path)?, &base_path, chain)?;
+      Ok(merge(base, document))
+    },
+    None => Ok(document),
+  }
+}
+
+fn base_path(document: &toml::Value, path: &Path) -> Result<Option<PathBuf>, ConfigError> {
+  match document.get(EXTENDS_KEY) {
+    Some(toml::Value::String(base_path)) => {
+      let parent = path.parent().unwrap_or_else(|| Path::new(""));
+      Ok(Some(parent.join(base_path)))
+    },
+    Some(value) => Err(ConfigError::Other(format!("Couldn't extend vibranium config: expected `{}` to be a string but got {}", EXTENDS_KEY, value.type_str()))),
+    None => Ok(None),
+  }
+}
+
+fn read(path: &Path) -> Result<toml::Value, ConfigError> {
+  let contents = fs::read_to_string(path).map_err(|err| ConfigError::Extends(path.to_path_buf(), err.to_string()))?;
+  migrations::parse(&contents).map_err(|err| ConfigError::Extends(path.to_path_buf(), err.to_string()))
+}
+
+fn canonical_path(path: &Path) -> PathBuf {
+  path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn merge(base: toml::Value, overlay: toml::Value) -> toml::Value {
+  match (base, overlay) {
+    (toml::Value::Table(mut base), toml::Value::Table(overlay)) => {
+      for (key, value) in overlay {
+        let value = match base.remove(&key) {
+          Some(base_value) => merge(base_value, value),
+          None => value,
+        };
+        base.insert(key, value);
+      }
+      toml::Value::Table(base)
+    },
+    (_base, overlay) => overlay,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+
+  extern crate tempfile;
+
+  use std::fs;
+  use std::path::PathBuf;
+  use tempfile::{tempdir, TempDir};
+  use super::{base_paths, resolve, resolve_base};
+  use crate::config::error::ConfigError;
+
+  fn create_files(files: Vec<(&str, &str)>) -> Result<(TempDir, PathBuf), Box<dyn std::error::Error>> {
+    let tmp_dir = tempdir()?;
+    for (name, contents) in files {
+      let path = tmp_dir.path().join(name);
+      fs::create_dir_all(path.parent().unwrap())?;
+      fs::write(path, contents)?;
+    }
+    let path = tmp_dir.path().to_path_buf();
+    Ok((tmp_dir, path))
+  }
+
+  fn resolve_file(path: PathBuf) -> Result<toml::Value, ConfigError> {
+    let document = toml::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    resolve(document, &path)
+  }
+
+  #[test]
+  fn it_should_merge_tables_deeply_and_replace_arrays() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, path) = create_files(vec![
+      ("base.vibranium.toml", "
+        [compiler]
+          cmd = \"solc\"
+          options = [\"--abi\", \"--bin\"]
+
+        [blockchain.connector]
+          protocol = \"rpc\"
+          host = \"localhost\"
+      "),
+      ("dapp/vibranium.toml", "
+        extends = \"../base.vibranium.toml\"
+
+        [compiler]
+          options = [\"--optimize\"]
+
+        [blockchain.connector]
+          host = \"10.0.0.1\"
+      "),
+    ])?;
+
+    let document = resolve_file(path.join("dapp").join("vibranium.toml"))?;
+
+    assert_eq!(document["compiler"]["cmd"].as_str(), Some("solc"));
+    assert_eq!(document["compiler"]["options"], toml::Value::Array(vec![toml::Value::String("--optimize".to_string())]));
+    assert_eq!(document["blockchain"]["connector"]["protocol"].as_str(), Some("rpc"));
+    assert_eq!(document["blockchain"]["connector"]["host"].as_str(), Some("10.0.0.1"));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_resolve_extends_recursively_relative_to_extending_file() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, path) = create_files(vec![
+      ("root.toml", "[sources]\nartifacts = \"artifacts\"\n"),
+      ("shared/base.toml", "extends = \"../root.toml\"\n[deployment]\ngas_limit = 1\n"),
+      ("dapp/vibranium.toml", "extends = \"../shared/base.toml\"\n"),
+    ])?;
+
+    let document = resolve_file(path.join("dapp").join("vibranium.toml"))?;
+
+    assert_eq!(document["sources"]["artifacts"].as_str(), Some("artifacts"));
+    assert_eq!(document["deployment"]["gas_limit"].as_integer(), Some(1));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_list_extended_files() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, path) = create_files(vec![
+      ("shared/base.toml", "extends = \"../missing.toml\"\n"),
+      ("dapp/vibranium.toml", "extends = \"../shared/base.toml\"\n"),
+      ("a.toml", "extends = \"b.toml\"\n"),
+      ("b.toml", "extends = \"a.toml\"\n"),
+    ])?;
+    let base_paths_of = |path: PathBuf| base_paths(&toml::from_str(&fs::read_to_string(&path).unwrap()).unwrap(), &path);
+
+    assert_eq!(base_paths_of(path.join("dapp").join("vibranium.toml")), vec![
+      path.join("dapp").join("../shared/base.toml"),
+      path.join("dapp").join("../shared/base.toml").parent().unwrap().join("../missing.toml"),
+    ]);
+    assert_eq!(base_paths_of(path.join("a.toml")), vec![path.join("b.toml")]);
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_detect_cycles() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, path) = create_files(vec![
+      ("a.toml", "extends = \"b.toml\"\n"),
+      ("b.toml", "extends = \"a.toml\"\n"),
+    ])?;
+
+    match resolve_file(path.join("a.toml")) {
+      Err(ConfigError::CyclicExtends(chain)) => assert_eq!(chain.len(), 3),
+      other => panic!("expected cyclic extends error, got {:?}", other),
+    }
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_fail_if_extended_file_does_not_exist() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, path) = create_files(vec![("a.toml", "extends = \"missing.toml\"\n")])?;
+
+    let error = resolve_file(path.join("a.toml")).unwrap_err();
+    assert!(error.to_string().contains("missing.toml"));
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_resolve_base_without_extending_document() -> Result<(), Box<dyn std::error::Error>> {
+    let (tmp_dir, path) = create_files(vec![
+      ("base.toml", "[sources]\nartifacts = \"artifacts\"\n"),
+    ])?;
+    let document: toml::Value = toml::from_str("extends = \"base.toml\"\n[sources]\nartifacts = \"build\"\n")?;
+
+    let base = resolve_base(&document, &path.join("vibranium.toml"))?.unwrap();
+    assert_eq!(base["sources"]["artifacts"].as_str(), Some("artifacts"));
+    assert!(resolve_base(&toml::Value::Table(Default::default()), &path.join("vibranium.toml"))?.is_none());
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+}
@@ -0,0 +1,169 @@
+use super::error::ConfigError;
+
+use std::path::PathBuf;
+use toml;
+use toml_edit::{self, DocumentMut};
+
+/// The version of the configuration format written by this version of Vibranium.
+pub const CONFIG_VERSION: i64 = 2;
+pub const VERSION_KEY: &str = "version";
+
+/// A single step that upgrades a configuration document from the previous version
+/// to `version`. Migrations operate on the editable document, so that comments and
+/// formatting of the configuration file are preserved.
+struct Migration {
+  version: i64,
+  description: &'static str,
+  migrate: fn(&mut DocumentMut),
+}
+
+// Configurations without a `version` key are considered to be of version 0.
+// New migrations are appended here together with an increment of `CONFIG_VERSION`.
+const MIGRATIONS: [Migration; 2] = [
+  Migration {
+    version: 1,
+    description: "Add `version` key",
+    migrate: |_document| (),
+  },
+  Migration {
+    version: 2,
+    description: "Remove default `compiler.options` to compile with solc's standard JSON interface",
+    migrate: remove_default_compiler_options,
+  },
+];
+
+/// Describes a configuration file that has been migrated by `Config::migrate`.
+#[derive(Debug)]
+pub struct MigrationSummary {
+  pub from_version: i64,
+  pub to_version: i64,
+  pub migrations: Vec<&'static str>,
+  pub backup_file: PathBuf,
+}
+
+// Command line options Vibranium passed to solc and solcjs by default in version 1. They're
+// kept here, as changes of the compilers' current defaults mustn't affect this migration.
+const V1_SOLC_OPTIONS: [&str; 4] = ["--abi", "--bin", "--overwrite", "-o"];
+const V1_SOLCJS_OPTIONS: [&str; 3] = ["--abi", "--bin", "-o"];
+
+// Projects used to be initialized with the command line options Vibranium passed to solc
+// by default. Compiler options disable the standard JSON interface, so they're removed
+// unless they've been changed.
+fn remove_default_compiler_options(document: &mut DocumentMut) {
+  let compiler = match document.get_mut("compiler").and_then(toml_edit::Item::as_table_like_mut) {
+    Some(compiler) => compiler,
+    None => return,
+  };
+
+  let options = compiler.get("options")
+    .and_then(toml_edit::Item::as_array)
+    .map(|options| options.iter().filter_map(|option| option.as_str().map(str::to_string)).collect::<Vec<String>>());
+
+  let is_default = options.map(|options| {
+    options == V1_SOLC_OPTIONS || options == V1_SOLCJS_OPTIONS
+  }).unwrap_or(false);
+
+  if is_default {
+    compiler.remove("options");
+  }
+}
+
+/// Returns the version of the given document.
+pub fn version_of(document: &DocumentMut) -> Result<i64, ConfigError> {
+  match document.get(VERSION_KEY) {
+    None => Ok(0),
+    Some(item) => item.as_integer().ok_or_else(|| {
+      ConfigError::Other(format!("Couldn't read vibranium config: expected `{}` to be an integer", VERSION_KEY))
+    }),
+  }
+}
+
+/// Upgrades the given document step by step to `CONFIG_VERSION` and returns the
+/// descriptions of the migrations that have been applied.
+pub fn migrate(document: &mut DocumentMut) -> Result<Vec<&'static str>, ConfigError> {
+  let version = version_of(document)?;
+
+  if version > CONFIG_VERSION {
+    return Err(ConfigError::UnsupportedVersion(version));
+  }
+
+  let applied = MIGRATIONS.iter()
+    .filter(|migration| migration.version > version)
+    .map(|migration| {
+      info!("Migrating configuration to version {}: {}", migration.version, migration.description);
+      (migration.migrate)(document);
+      migration.description
+    })
+    .collect::<Vec<&'static str>>();
+
+  if !applied.is_empty() {
+    document[VERSION_KEY] = toml_edit::value(CONFIG_VERSION);
+  }
+
+  Ok(applied)
+}
+
+/// Parses the contents of a configuration file and migrates them to `CONFIG_VERSION`.
+pub fn parse(contents: &str) -> Result<toml::Value, ConfigError> {
+  let mut document = contents.parse::<DocumentMut>().map_err(ConfigError::Parse)?;
+  migrate(&mut document)?;
+  toml::from_str(&document.to_string()).map_err(ConfigError::Deserialization)
+}
+
+#[cfg(test)]
+mod tests {
+
+  use toml_edit::DocumentMut;
+  use super::{migrate, parse, version_of, CONFIG_VERSION};
+  use crate::config::error::ConfigError;
+
+  #[test]
+  fn it_should_treat_documents_without_version_as_version_zero() {
+    let document = "[sources]\nartifacts = \"artifacts\"\n".parse::<DocumentMut>().unwrap();
+    assert_eq!(version_of(&document).unwrap(), 0);
+  }
+
+  #[test]
+  fn it_should_migrate_to_latest_version_and_preserve_comments() {
+    let mut document = "# My project\n[sources]\nartifacts = \"artifacts\" # build output\n".parse::<DocumentMut>().unwrap();
+
+    let applied = migrate(&mut document).unwrap();
+
+    assert_eq!(applied.len(), CONFIG_VERSION as usize);
+    assert_eq!(version_of(&document).unwrap(), CONFIG_VERSION);
+    assert_eq!(document.to_string(), format!("version = {}\n# My project\n[sources]\nartifacts = \"artifacts\" # build output\n", CONFIG_VERSION));
+  }
+
+  #[test]
+  fn it_should_remove_default_compiler_options_only() {
+    let mut document = "[compiler]\ncmd = \"solc\"\noptions = [\"--abi\", \"--bin\", \"--overwrite\", \"-o\"]\n".parse::<DocumentMut>().unwrap();
+    migrate(&mut document).unwrap();
+    assert_eq!(document.to_string(), format!("version = {}\n[compiler]\ncmd = \"solc\"\n", CONFIG_VERSION));
+
+    let mut document = "version = 1\n[compiler]\ncmd = \"solcjs\"\noptions = [\"--abi\", \"--bin\", \"-o\"]\n".parse::<DocumentMut>().unwrap();
+    migrate(&mut document).unwrap();
+    assert_eq!(document.to_string(), format!("version = {}\n[compiler]\ncmd = \"solcjs\"\n", CONFIG_VERSION));
+
+    let contents = "version = 1\n[compiler]\ncmd = \"solc\"\noptions = [\"--optimize\", \"-o\"]\n";
+    let mut document = contents.parse::<DocumentMut>().unwrap();
+    migrate(&mut document).unwrap();
+    assert_eq!(document.to_string(), contents.replace("version = 1", &format!("version = {}", CONFIG_VERSION)));
+  }
+
+  #[test]
+  fn it_should_not_migrate_latest_version() {
+    let contents = format!("version = {}\n", CONFIG_VERSION);
+    let mut document = contents.parse::<DocumentMut>().unwrap();
+
+    assert!(migrate(&mut document).unwrap().is_empty());
+    assert_eq!(document.to_string(), contents);
+  }
+
+  #[test]
+  fn it_should_fail_for_newer_versions() {
+    match parse(&format!("version = {}\n", CONFIG_VERSION + 1)) {
+      Err(ConfigError::UnsupportedVersion(version)) => assert_eq!(version, CONFIG_VERSION + 1),
+      other => panic!("expected unsupported version error, got {:?}", other),
+    }
+  }
+}
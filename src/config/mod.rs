@@ -1,20 +1,31 @@
 pub mod error;
+pub mod schema;
+pub mod migrations;
+mod extends;
+pub mod validation;
+mod variables;
 
 use crate::blockchain;
 use crate::compiler;
+use crate::compiler::strategy::CompilerStrategy;
 use crate::project_generator;
 
+use std::env;
 use std::fs;
 use std::io::Write;
 use std::path::PathBuf;
 use std::default::Default;
+use std::collections::BTreeMap;
 use toml;
-use toml_query::set::TomlValueSetExt;
-use toml_query::delete::TomlValueDeleteExt;
-use toml_query::insert::TomlValueInsertExt;
-use toml_query::error::Error::IdentifierNotFoundInDocument;
+use toml_edit::{DocumentMut, Item};
+use toml_query::read::TomlValueReadExt;
 use blockchain::connector::BlockchainConnectorConfig;
-use project_generator::VIBRANIUM_PROJECT_DIRECTORY;
+use project_generator::{VIBRANIUM_PROJECT_DIRECTORY, DEFAULT_ENVIRONMENT};
+
+pub use schema::parse_value;
+use schema::ValueKind;
+pub use migrations::{CONFIG_VERSION, MigrationSummary};
+pub use validation::ConfigDiagnostic;
 
 pub const VIBRANIUM_CONFIG_FILE: &str = "vibranium.toml";
 pub const DEFAULT_ARTIFACTS_DIRECTORY: &str = "artifacts";
@@ -22,34 +33,121 @@ pub const DEFAULT_CONTRACTS_DIRECTORY: &str = "contracts";
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ProjectConfig {
+  pub version: Option<i64>,
   pub sources: ProjectSourcesConfig,
   pub compiler: Option<ProjectCmdExecutionConfig>,
   pub blockchain: Option<ProjectBlockchainConfig>,
   pub deployment: Option<ProjectDeploymentConfig>,
+  pub environments: Option<BTreeMap<String, ProjectEnvironmentConfig>>,
 }
 
 impl Default for ProjectConfig {
   fn default() -> Self {
     ProjectConfig {
+      version: Some(CONFIG_VERSION),
       sources: ProjectSourcesConfig::default(),
       compiler: Some(ProjectCmdExecutionConfig::default()),
       blockchain: Some(ProjectBlockchainConfig::default()),
-      deployment: None
+      deployment: None,
+      environments: None,
+    }
+  }
+}
+
+impl ProjectConfig {
+  /// Applies the overrides of the given environment on top of the base configuration.
+  ///
+  /// The default environment doesn't have to be defined in `[environments]`. If it's
+  /// missing, the base configuration is returned as is.
+  ///
+  /// Exclude patterns of sources are added to the ones of the base configuration, so that
+  /// environments can exclude further source files.
+  pub fn with_environment(mut self, environment: &str) -> Result<ProjectConfig, error::ConfigError> {
+    let environment_config = match self.environments.as_mut().and_then(|environments| environments.remove(environment)) {
+      Some(environment_config) => environment_config,
+      None if environment == DEFAULT_ENVIRONMENT => return Ok(self),
+      None => return Err(error::ConfigError::UnknownEnvironment(environment.to_string())),
+    };
+
+    if let Some(sources_config) = environment_config.sources {
+      self.sources.artifacts = sources_config.artifacts.unwrap_or(self.sources.artifacts);
+      self.sources.smart_contracts = sources_config.smart_contracts.unwrap_or(self.sources.smart_contracts);
+      self.sources.exclude = match (self.sources.exclude, sources_config.exclude) {
+        (Some(mut base), Some(exclude)) => {
+          base.extend(exclude);
+          Some(base)
+        },
+        (base, exclude) => exclude.or(base),
+      };
+    }
+
+    if let Some(blockchain_config) = environment_config.blockchain {
+      self.blockchain = match self.blockchain {
+        Some(base) => Some(ProjectBlockchainConfig {
+          cmd: blockchain_config.cmd.or(base.cmd),
+          options: blockchain_config.options.or(base.options),
+          connector: blockchain_config.connector.or(base.connector),
+        }),
+        None => Some(blockchain_config),
+      };
+    }
+
+    if let Some(deployment_config) = environment_config.deployment {
+      let mut base = self.deployment.unwrap_or(ProjectDeploymentConfig {
+        tx_confirmations: None,
+        gas_price: None,
+        gas_limit: None,
+        tracking_enabled: None,
+        smart_contracts: vec![],
+      });
+
+      base.tx_confirmations = deployment_config.tx_confirmations.or(base.tx_confirmations);
+      base.gas_price = deployment_config.gas_price.or(base.gas_price);
+      base.gas_limit = deployment_config.gas_limit.or(base.gas_limit);
+      base.tracking_enabled = deployment_config.tracking_enabled.or(base.tracking_enabled);
+
+      for smart_contract in deployment_config.smart_contracts.unwrap_or_default() {
+        match base.smart_contracts.iter_mut().find(|existing| existing.name == smart_contract.name) {
+          Some(existing) => existing.merge(smart_contract),
+          None => base.smart_contracts.push(smart_contract),
+        }
+      }
+
+      self.deployment = Some(base);
     }
+
+    Ok(self)
   }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ProjectCmdExecutionConfig {
   pub cmd: Option<String>,
-  pub options: Option<Vec<String>>
+  pub options: Option<Vec<String>>,
+  /// Version requirement for solc releases installed in `~/.vibranium/compilers`, in
+  /// addition to the version pragmas of the source files.
+  pub version: Option<String>,
+  /// Import remappings, e.g. `@oz/=lib/openzeppelin/contracts/`. Relative targets are
+  /// resolved against the project path.
+  pub remappings: Option<Vec<String>>,
+  /// Maximum runtime size of Smart Contracts in bytes, defaults to the EIP-170 limit.
+  pub max_contract_size: Option<usize>,
+  /// Maximum initcode size of Smart Contracts in bytes, defaults to the EIP-3860 limit.
+  pub max_initcode_size: Option<usize>,
+  /// Fails compilation if a Smart Contract exceeds a size limit, instead of warning.
+  pub fail_on_size_limit: Option<bool>,
 }
 
 impl Default for ProjectCmdExecutionConfig {
   fn default() -> Self {
     ProjectCmdExecutionConfig {
-      cmd: Some(compiler::support::SupportedCompilers::Solc.to_string()),
-      options: Some(compiler::support::default_options_from(compiler::support::SupportedCompilers::Solc))
+      cmd: Some(compiler::support::Solc.name().to_string()),
+      options: None,
+      version: None,
+      remappings: None,
+      max_contract_size: None,
+      max_initcode_size: None,
+      fail_on_size_limit: None,
     }
   }
 }
@@ -75,13 +173,18 @@ impl Default for ProjectBlockchainConfig {
 pub struct ProjectSourcesConfig {
   pub artifacts: String,
   pub smart_contracts: Vec<String>,
+  pub exclude: Option<Vec<String>>,
 }
 
 impl Default for ProjectSourcesConfig {
   fn default() -> Self {
     ProjectSourcesConfig {
       artifacts: DEFAULT_ARTIFACTS_DIRECTORY.to_string(),
-      smart_contracts: vec![DEFAULT_CONTRACTS_DIRECTORY.to_string() + "/*.sol"],
+      smart_contracts: vec![
+        DEFAULT_CONTRACTS_DIRECTORY.to_string() + "/*.sol",
+        DEFAULT_CONTRACTS_DIRECTORY.to_string() + "/*.vy",
+      ],
+      exclude: None,
     }
   }
 }
@@ -92,6 +195,7 @@ pub struct ProjectDeploymentConfig {
   pub gas_price: Option<usize>,
   pub gas_limit: Option<usize>,
   pub tracking_enabled: Option<bool>,
+  #[serde(default)]
   pub smart_contracts: Vec<SmartContractConfig>,
 }
 
@@ -107,17 +211,53 @@ pub struct SmartContractConfig {
   pub bytecode_path: Option<String>,
 }
 
+impl SmartContractConfig {
+  fn merge(&mut self, other: SmartContractConfig) {
+    self.address = other.address.or_else(|| self.address.take());
+    self.args = other.args.or_else(|| self.args.take());
+    self.gas_price = other.gas_price.or(self.gas_price);
+    self.gas_limit = other.gas_limit.or(self.gas_limit);
+    self.instance_of = other.instance_of.or_else(|| self.instance_of.take());
+    self.abi_path = other.abi_path.or_else(|| self.abi_path.take());
+    self.bytecode_path = other.bytecode_path.or_else(|| self.bytecode_path.take());
+  }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SmartContractArg {
   pub value: String,
   pub kind: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ProjectEnvironmentConfig {
+  pub sources: Option<ProjectEnvironmentSourcesConfig>,
+  pub blockchain: Option<ProjectBlockchainConfig>,
+  pub deployment: Option<ProjectEnvironmentDeploymentConfig>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ProjectEnvironmentSourcesConfig {
+  pub artifacts: Option<String>,
+  pub smart_contracts: Option<Vec<String>>,
+  pub exclude: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ProjectEnvironmentDeploymentConfig {
+  pub tx_confirmations: Option<usize>,
+  pub gas_price: Option<usize>,
+  pub gas_limit: Option<usize>,
+  pub tracking_enabled: Option<bool>,
+  pub smart_contracts: Option<Vec<SmartContractConfig>>,
+}
+
 #[derive(Default, Debug)]
 pub struct Config {
   pub project_path: PathBuf,
   pub vibranium_dir_path: PathBuf,
   pub config_file: PathBuf,
+  pub environment: Option<String>,
 }
 
 impl Config {
@@ -125,7 +265,8 @@ impl Config {
     Config {
       project_path: path.clone(),
       vibranium_dir_path: path.clone().join(VIBRANIUM_PROJECT_DIRECTORY),
-      config_file: path.join(VIBRANIUM_CONFIG_FILE)
+      config_file: path.join(VIBRANIUM_CONFIG_FILE),
+      environment: None,
     }
   }
 
@@ -133,61 +274,656 @@ impl Config {
     self.config_file.exists()
   }
 
+  pub fn environment(&self) -> &str {
+    self.environment.as_deref().unwrap_or(DEFAULT_ENVIRONMENT)
+  }
+
+  /// Reads the project configuration with the overrides of the active environment applied.
+  ///
+  /// If the configuration `extends` another configuration file, it's merged on top of it.
+  ///
+  /// `${VAR}` and `${VAR:-default}` in string values are replaced with the values of the
+  /// corresponding environment variables. Environments other than the active one are ignored,
+  /// so they can reference variables that are only set where they're used. Afterwards, any option can be overridden by
+  /// environment variables in the form of `VIBRANIUM_<SECTION>__<KEY>`, for example
+  /// `VIBRANIUM_BLOCKCHAIN__CONNECTOR__HOST`.
   pub fn read(&self) -> Result<ProjectConfig, error::ConfigError> {
-    toml::from_str(&fs::read_to_string(&self.config_file)?).map_err(error::ConfigError::Deserialization)
+    let mut document = self.read_document()?;
+    retain_environment(&mut document, self.environment());
+    variables::interpolate(&mut document, &|name| env::var(name).ok())?;
+
+    let project_config = document.try_into::<ProjectConfig>()
+      .map_err(error::ConfigError::Deserialization)?
+      .with_environment(self.environment())?;
+
+    let overrides = variables::overrides(env::vars())?;
+
+    if overrides.is_empty() {
+      return Ok(project_config);
+    }
+
+    let mut document = toml::Value::try_from(project_config)?;
+    for (option, value) in overrides {
+      info!("Overriding configuration option {} from environment", &option);
+      variables::apply_override(&mut document, &option, value)?;
+    }
+    document.try_into::<ProjectConfig>().map_err(error::ConfigError::Deserialization)
   }
 
-  pub fn write(&self, option: String, value: toml::Value) -> Result<(), error::ConfigError> {
-    let mut config = self.try_from_config_file()?;
+  /// Returns the configuration file and the files it `extends`, directly or transitively.
+  pub fn files(&self) -> Result<Vec<PathBuf>, error::ConfigError> {
+    let document = migrations::parse(&fs::read_to_string(&self.config_file)?)?;
+    let mut files = vec![self.config_file.clone()];
+    files.extend(extends::base_paths(&document, &self.config_file));
+    Ok(files)
+  }
 
-    if let Err(err) = config.set(&option, value.clone()) {
-      match err {
-        IdentifierNotFoundInDocument(_message) => {
-          config.insert(&option, value.clone()).map_err(error::ConfigError::Query)?;
-        },
-        _ => Err(error::ConfigError::Query(err))?
-      }
+  /// Checks the configuration file for problems, such as unknown options, values of the
+  /// wrong type or references to undefined Smart Contracts. Options that are overridden by
+  /// `VIBRANIUM_<SECTION>__<KEY>` environment variables aren't checked.
+  pub fn check(&self) -> Result<Vec<ConfigDiagnostic>, error::ConfigError> {
+    let source = fs::read_to_string(&self.config_file)?;
+    let base = match toml::from_str::<toml::Value>(&source) {
+      Ok(document) => extends::resolve_base(&document, &self.config_file)?,
+      Err(_err) => None,
+    };
+    let overrides = variables::overrides(env::vars())?;
+    Ok(validation::validate(&source, base.as_ref())
+      .into_iter()
+      .filter(|diagnostic| !overrides.iter().any(|(option, _value)| is_same_or_nested_option(&diagnostic.option, option)))
+      .collect())
+  }
+
+  /// Like `Config::check`, but fails with all errors found. Warnings are returned otherwise.
+  pub fn validate(&self) -> Result<Vec<ConfigDiagnostic>, error::ConfigError> {
+    let (errors, warnings): (Vec<ConfigDiagnostic>, Vec<ConfigDiagnostic>) = self.check()?
+      .into_iter()
+      .partition(ConfigDiagnostic::is_error);
+    if errors.is_empty() {
+      Ok(warnings)
+    } else {
+      Err(error::ConfigError::Invalid(errors))
     }
+  }
 
-    config.try_into::<ProjectConfig>()
-      .map_err(error::ConfigError::Deserialization)
-      .and_then(|cfg| {
-        let config_toml = toml::to_string(&cfg)?;
-        let mut config_file = fs::File::create(&self.config_file)?;
-        config_file.write_all(config_toml.as_bytes()).map_err(error::ConfigError::Io)
-      })?;
+  pub fn get(&self, option: String) -> Result<Option<toml::Value>, error::ConfigError> {
+    let config = self.read_document()?;
+    let value = config.read(&option).map_err(error::ConfigError::Query)?;
+    Ok(value.cloned())
+  }
 
-    Ok(())
+  /// Returns all configuration options as flattened, dotted keys that can be passed
+  /// to `Config::get`. Entries of arrays of tables are addressed by index, e.g.
+  /// `deployment.smart_contracts.[0].name`.
+  pub fn list(&self) -> Result<Vec<(String, toml::Value)>, error::ConfigError> {
+    let config = self.read_document()?;
+    let mut entries = vec![];
+    flatten(None, &config, &mut entries);
+    Ok(entries)
+  }
+
+  /// Sets the given option in the configuration file. The file is edited in place,
+  /// so comments, formatting and options unknown to `ProjectConfig` are preserved.
+  /// Configuration files that are extended are never modified.
+  ///
+  /// Only options of the schema of `ProjectConfig` can be set.
+  pub fn write(&self, option: String, value: toml::Value) -> Result<(), error::ConfigError> {
+    if ValueKind::project_config().lookup(&option).is_none() {
+      return Err(error::ConfigError::UnknownOption(option));
+    }
+    let mut document = self.read_editable_document()?;
+
+    let item = option.split('.')
+      .try_fold(document.as_item_mut(), |item, segment| child_item_mut(item, segment))
+      .ok_or_else(|| error::ConfigError::Other(format!("Couldn't set configuration option '{}'", &option)))?;
+
+    set_item(item, value);
+    self.try_into_config_file(&document)
   }
 
   pub fn remove(&self, option: String) -> Result<(), error::ConfigError> {
-    let mut config = self.try_from_config_file()?;
+    let mut document = self.read_editable_document()?;
+    let mut segments = option.split('.').collect::<Vec<&str>>();
+    let key = segments.pop().unwrap_or_default();
+
+    let parent = segments.iter()
+      .try_fold(document.as_item_mut(), |item, segment| existing_child_item_mut(item, segment));
+
+    let removed = match parent {
+      Some(parent) => remove_child_item(parent, key).map_err(|_| error::ConfigError::Deletion(option.to_owned()))?,
+      None => false,
+    };
+
+    if !removed {
+      info!("Couldn't delete unsupported option {}", &option);
+    }
+
+    self.try_into_config_file(&document)
+  }
+
+  /// Migrates the configuration file to `CONFIG_VERSION`. The original file is kept as
+  /// backup next to it. Returns `None` if the configuration is already up to date.
+  pub fn migrate(&self) -> Result<Option<MigrationSummary>, error::ConfigError> {
+    let contents = fs::read_to_string(&self.config_file)?;
+    let mut document = contents.parse::<DocumentMut>().map_err(error::ConfigError::Parse)?;
+    let from_version = migrations::version_of(&document)?;
+    let applied = migrations::migrate(&mut document)?;
+
+    if applied.is_empty() {
+      return Ok(None);
+    }
+
+    let backup_file = self.config_file.with_file_name(format!("{}.v{}.bak", VIBRANIUM_CONFIG_FILE, from_version));
+    info!("Creating backup of configuration file: {:?}", &backup_file);
+    fs::write(&backup_file, &contents)?;
+    self.try_into_config_file(&document)?;
+
+    Ok(Some(MigrationSummary {
+      from_version,
+      to_version: CONFIG_VERSION,
+      migrations: applied,
+      backup_file,
+    }))
+  }
+
+  // Reads the configuration file with the configuration it `extends` merged in.
+  fn read_document(&self) -> Result<toml::Value, error::ConfigError> {
+    let document = migrations::parse(&fs::read_to_string(&self.config_file)?)?;
+    extends::resolve(document, &self.config_file)
+  }
+
+  fn read_editable_document(&self) -> Result<DocumentMut, error::ConfigError> {
+    fs::read_to_string(&self.config_file)?.parse::<DocumentMut>().map_err(error::ConfigError::Parse)
+  }
+
+  fn try_into_config_file(&self, document: &DocumentMut) -> Result<(), error::ConfigError> {
+    let config_toml = document.to_string();
+    let resolved_document = extends::resolve(migrations::parse(&config_toml)?, &self.config_file)?;
+    resolved_document.try_into::<ProjectConfig>().map_err(error::ConfigError::Deserialization)?;
+    let mut config_file = fs::File::create(&self.config_file)?;
+    config_file.write_all(config_toml.as_bytes()).map_err(error::ConfigError::Io)
+  }
+}
+
+
+// Removes all environments except the given one from a configuration document.
+fn retain_environment(document: &mut toml::Value, environment: &str) {
+  if let Some(environments) = document.get_mut("environments").and_then(toml::Value::as_table_mut) {
+    let unselected = environments.keys().filter(|name| *name != environment).cloned().collect::<Vec<String>>();
+    for name in unselected {
+      environments.remove(&name);
+    }
+  }
+}
+
+// Whether `option` is `parent` or one of its nested options, e.g. `sources.artifacts` of `sources`.
+fn is_same_or_nested_option(option: &str, parent: &str) -> bool {
+  option == parent || (option.starts_with(parent) && option[parent.len()..].starts_with('.'))
+}
+
+fn parse_index(segment: &str) -> Option<usize> {
+  if segment.starts_with('[') && segment.ends_with(']') {
+    segment[1..segment.len() - 1].parse::<usize>().ok()
+  } else {
+    None
+  }
+}
+
+// Returns the child item for `segment`, creating missing tables along the way.
+// Entries of arrays are addressed with `[N]` segments and have to exist.
+fn child_item_mut<'a>(item: &'a mut Item, segment: &str) -> Option<&'a mut Item> {
+  if let Some(index) = parse_index(segment) {
+    return item.get_mut(index);
+  }
+
+  if item.is_none() {
+    let mut table = toml_edit::Table::new();
+    table.set_implicit(true);
+    *item = Item::Table(table);
+  }
+
+  // Missing children of inline tables have to be inline tables themselves.
+  let placeholder = if item.is_inline_table() {
+    Item::Value(toml_edit::Value::InlineTable(toml_edit::InlineTable::new()))
+  } else {
+    Item::None
+  };
 
-    if let Err(err) = config.delete(&option) {
-      match err {
-        IdentifierNotFoundInDocument(field) => {
-          info!("Couldn't delete unsupported option {}", field);
+  item.as_table_like_mut().map(|table| table.entry(segment).or_insert(placeholder))
+}
+
+fn existing_child_item_mut<'a>(item: &'a mut Item, segment: &str) -> Option<&'a mut Item> {
+  match parse_index(segment) {
+    Some(index) => item.get_mut(index),
+    None => item.as_table_like_mut().and_then(|table| table.get_mut(segment)),
+  }
+}
+
+// Removes the child item for `segment` and returns whether it existed.
+// Tables and arrays can only be removed when they are empty.
+fn remove_child_item(parent: &mut Item, segment: &str) -> Result<bool, ()> {
+  let is_empty = match existing_child_item_mut(parent, segment) {
+    None => return Ok(false),
+    Some(Item::Table(table)) => table.is_empty(),
+    Some(Item::ArrayOfTables(array)) => array.is_empty(),
+    Some(Item::Value(toml_edit::Value::Array(array))) => array.is_empty(),
+    Some(Item::Value(toml_edit::Value::InlineTable(table))) => table.is_empty(),
+    Some(_) => true,
+  };
+
+  if !is_empty {
+    return Err(());
+  }
+
+  match (parse_index(segment), parent) {
+    (Some(index), Item::ArrayOfTables(array)) => array.remove(index),
+    (Some(index), Item::Value(toml_edit::Value::Array(array))) => { array.remove(index); },
+    (None, parent) => { parent.as_table_like_mut().and_then(|table| table.remove(segment)); },
+    _ => return Ok(false),
+  };
+  Ok(true)
+}
+
+// Replaces the given item with `value`, keeping the item's decoration (e.g. comments)
+// and its table style where possible.
+fn set_item(item: &mut Item, value: toml::Value) {
+  let new_item = match (&*item, value) {
+    (Item::Table(existing), toml::Value::Table(table)) => {
+      let mut new_table = into_table(table);
+      *new_table.decor_mut() = existing.decor().clone();
+      Item::Table(new_table)
+    },
+    (Item::ArrayOfTables(_), toml::Value::Array(ref array)) if array.iter().all(toml::Value::is_table) => {
+      let mut array_of_tables = toml_edit::ArrayOfTables::new();
+      for table in array.iter().cloned() {
+        if let toml::Value::Table(table) = table {
+          array_of_tables.push(into_table(table));
         }
-        _ => Err(error::ConfigError::Deletion(err))?
       }
-    }
+      Item::ArrayOfTables(array_of_tables)
+    },
+    (existing, value) => {
+      let mut new_value = into_value(value);
+      if let Item::Value(existing) = existing {
+        *new_value.decor_mut() = existing.decor().clone();
+      }
+      Item::Value(new_value)
+    },
+  };
+  *item = new_item;
+}
 
-    self.try_into_config_file(config.clone())?;
-    Ok(())
+fn into_table(table: toml::value::Table) -> toml_edit::Table {
+  let mut new_table = toml_edit::Table::new();
+  for (key, value) in table {
+    new_table.insert(&key, Item::Value(into_value(value)));
   }
+  new_table
+}
 
-  fn try_from_config_file(&self) -> Result<toml::Value, error::ConfigError> {
-    toml::Value::try_from(self.read()?).map_err(error::ConfigError::Serialization)
+fn into_value(value: toml::Value) -> toml_edit::Value {
+  match value {
+    toml::Value::String(value) => value.into(),
+    toml::Value::Integer(value) => value.into(),
+    toml::Value::Float(value) => value.into(),
+    toml::Value::Boolean(value) => value.into(),
+    toml::Value::Datetime(value) => {
+      let datetime = value.to_string();
+      datetime.parse::<toml_edit::Datetime>().map(toml_edit::Value::from).unwrap_or_else(|_| datetime.into())
+    },
+    toml::Value::Array(values) => toml_edit::Value::Array(values.into_iter().map(into_value).collect()),
+    toml::Value::Table(table) => toml_edit::Value::InlineTable(table.into_iter().map(|(key, value)| (key, into_value(value))).collect()),
   }
+}
+
+fn flatten(prefix: Option<String>, value: &toml::Value, entries: &mut Vec<(String, toml::Value)>) {
+  let key = |name: String| match &prefix {
+    Some(prefix) => format!("{}.{}", prefix, name),
+    None => name,
+  };
 
-  fn try_into_config_file(&self, config: toml::Value) -> Result<(), error::ConfigError> {
-    config.try_into::<ProjectConfig>()
-      .map_err(error::ConfigError::Deserialization)
-      .and_then(|cfg| {
-        let config_toml = toml::to_string(&cfg)?;
-        let mut config_file = fs::File::create(&self.config_file)?;
-        config_file.write_all(config_toml.as_bytes()).map_err(error::ConfigError::Io)
-      })
+  match value {
+    toml::Value::Table(table) => {
+      for (name, value) in table {
+        flatten(Some(key(name.to_owned())), value, entries);
+      }
+    },
+    toml::Value::Array(array) if !array.is_empty() && array.iter().all(toml::Value::is_table) => {
+      for (i, value) in array.iter().enumerate() {
+        flatten(Some(key(format!("[{}]", i))), value, entries);
+      }
+    },
+    _ => entries.push((prefix.clone().unwrap_or_default(), value.clone())),
   }
 }
 
+#[cfg(test)]
+mod tests {
+
+  extern crate tempfile;
+
+  use std::fs;
+  use tempfile::{tempdir, TempDir};
+  use super::Config;
+
+  const COMMENTED_CONFIG: &str = "# Project configuration
+[sources]
+artifacts = \"artifacts\" # relative to the project
+smart_contracts = [\"contracts/*.sol\"]
+custom = \"unknown to vibranium\"
+
+[compiler]
+cmd = \"solc\"
+
+# Deployment settings
+[deployment]
+gas_limit = 1000
+
+[[deployment.smart_contracts]]
+name = \"A\"
+";
+
+  fn create_test_config(contents: &str) -> Result<(TempDir, Config), Box<dyn std::error::Error>> {
+    let tmp_dir = tempdir()?;
+    let config = Config::new(tmp_dir.path().to_path_buf());
+    fs::write(&config.config_file, contents)?;
+    Ok((tmp_dir, config))
+  }
+
+  mod read {
+
+    use super::create_test_config;
+
+    #[test]
+    fn it_should_only_interpolate_variables_of_the_active_environment() -> Result<(), Box<dyn std::error::Error>> {
+      let (tmp_dir, mut config) = create_test_config("
+        [sources]
+          artifacts = \"artifacts\"
+          smart_contracts = [\"contracts/*.sol\"]
+        [environments.production.blockchain.connector]
+          host = \"${VIBRANIUM_TEST_UNSET_PROD_HOST}\"
+      ")?;
+
+      assert!(config.read().is_ok());
+      config.environment = Some("production".to_string());
+      assert!(config.read().is_err());
+
+      tmp_dir.close()?;
+      Ok(())
+    }
+  }
+
+  mod validate {
+
+    use super::{create_test_config, COMMENTED_CONFIG};
+    use crate::config::error::ConfigError;
+    use crate::config::is_same_or_nested_option;
+
+    #[test]
+    fn it_should_only_fail_on_errors() -> Result<(), Box<dyn std::error::Error>> {
+      let (tmp_dir, config) = create_test_config(COMMENTED_CONFIG)?;
+      let warnings = config.validate()?;
+      assert_eq!(warnings.len(), 1);
+      assert_eq!(warnings[0].option, "sources.custom");
+
+      let (_other_tmp_dir, config) = create_test_config(&COMMENTED_CONFIG.replace("gas_limit = 1000", "gas_limit = \"a lot\""))?;
+      match config.validate() {
+        Err(ConfigError::Invalid(errors)) => {
+          assert_eq!(errors.len(), 1);
+          assert_eq!(errors[0].option, "deployment.gas_limit");
+        },
+        _ => panic!("Expected configuration to be invalid"),
+      }
+
+      tmp_dir.close()?;
+      Ok(())
+    }
+
+    #[test]
+    fn it_should_match_overridden_options() {
+      assert!(is_same_or_nested_option("sources.artifacts", "sources.artifacts"));
+      assert!(is_same_or_nested_option("deployment.smart_contracts.[0].address", "deployment.smart_contracts"));
+      assert!(!is_same_or_nested_option("sources.artifacts_dir", "sources.artifacts"));
+      assert!(!is_same_or_nested_option("sources", "sources.artifacts"));
+    }
+  }
+
+  mod write {
+
+    use std::fs;
+    use super::{create_test_config, COMMENTED_CONFIG};
+    use crate::config::error::ConfigError;
+
+    #[test]
+    fn it_should_preserve_comments_and_unknown_options() -> Result<(), Box<dyn std::error::Error>> {
+      let (tmp_dir, config) = create_test_config(COMMENTED_CONFIG)?;
+
+      config.write("sources.artifacts".to_string(), toml::Value::String("build".to_string()))?;
+
+      let contents = fs::read_to_string(&config.config_file)?;
+      assert_eq!(contents, COMMENTED_CONFIG.replace("artifacts = \"artifacts\"", "artifacts = \"build\""));
+
+      tmp_dir.close()?;
+      Ok(())
+    }
+
+    #[test]
+    fn it_should_insert_new_options() -> Result<(), Box<dyn std::error::Error>> {
+      let (tmp_dir, config) = create_test_config(COMMENTED_CONFIG)?;
+
+      config.write("deployment.smart_contracts.[0].gas_price".to_string(), toml::Value::Integer(5))?;
+      config.write("environments.staging.deployment.gas_limit".to_string(), toml::Value::Integer(10))?;
+
+      let contents = fs::read_to_string(&config.config_file)?;
+      assert!(contents.starts_with("# Project configuration"));
+      assert!(contents.contains("name = \"A\"\ngas_price = 5\n"));
+      assert!(contents.contains("[environments.staging.deployment]\ngas_limit = 10\n"));
+      assert!(!contents.contains("[environments]\n"));
+
+      let project_config = config.read()?;
+      assert_eq!(project_config.deployment.unwrap().smart_contracts[0].gas_price, Some(5));
+
+      tmp_dir.close()?;
+      Ok(())
+    }
+
+    #[test]
+    fn it_should_only_write_to_extending_config_file() -> Result<(), Box<dyn std::error::Error>> {
+      let (tmp_dir, config) = create_test_config("extends = \"base.toml\"\n")?;
+      let base_config_file = config.project_path.join("base.toml");
+      fs::write(&base_config_file, COMMENTED_CONFIG)?;
+
+      config.write("sources.artifacts".to_string(), toml::Value::String("build".to_string()))?;
+
+      assert_eq!(fs::read_to_string(&base_config_file)?, COMMENTED_CONFIG);
+      assert_eq!(fs::read_to_string(&config.config_file)?, "extends = \"base.toml\"\n\n[sources]\nartifacts = \"build\"\n");
+
+      let project_config = config.read()?;
+      assert_eq!(project_config.sources.artifacts, "build");
+      assert_eq!(project_config.sources.smart_contracts, vec!["contracts/*.sol"]);
+
+      tmp_dir.close()?;
+      Ok(())
+    }
+
+    #[test]
+    fn it_should_not_write_invalid_configuration() -> Result<(), Box<dyn std::error::Error>> {
+      let (tmp_dir, config) = create_test_config(COMMENTED_CONFIG)?;
+
+      assert!(config.write("deployment.gas_limit".to_string(), toml::Value::String("a lot".to_string())).is_err());
+      assert_eq!(fs::read_to_string(&config.config_file)?, COMMENTED_CONFIG);
+
+      tmp_dir.close()?;
+      Ok(())
+    }
+
+    #[test]
+    fn it_should_not_write_unknown_options() -> Result<(), Box<dyn std::error::Error>> {
+      let (tmp_dir, config) = create_test_config(COMMENTED_CONFIG)?;
+
+      match config.write("unknwn".to_string(), toml::Value::String("foo".to_string())) {
+        Err(ConfigError::UnknownOption(option)) => assert_eq!(option, "unknwn"),
+        _ => panic!("Expected unknown option error"),
+      }
+      assert!(config.write("sources.custom".to_string(), toml::Value::String("foo".to_string())).is_err());
+      assert_eq!(fs::read_to_string(&config.config_file)?, COMMENTED_CONFIG);
+
+      tmp_dir.close()?;
+      Ok(())
+    }
+  }
+
+  mod remove {
+
+    use std::fs;
+    use super::{create_test_config, COMMENTED_CONFIG};
+
+    #[test]
+    fn it_should_preserve_comments_and_unknown_options() -> Result<(), Box<dyn std::error::Error>> {
+      let (tmp_dir, config) = create_test_config(COMMENTED_CONFIG)?;
+
+      config.remove("compiler.cmd".to_string())?;
+
+      let contents = fs::read_to_string(&config.config_file)?;
+      assert_eq!(contents, COMMENTED_CONFIG.replace("cmd = \"solc\"\n", ""));
+
+      tmp_dir.close()?;
+      Ok(())
+    }
+
+    #[test]
+    fn it_should_not_remove_options_that_are_not_empty() -> Result<(), Box<dyn std::error::Error>> {
+      let (tmp_dir, config) = create_test_config(COMMENTED_CONFIG)?;
+
+      assert!(config.remove("deployment.smart_contracts".to_string()).is_err());
+      assert!(config.remove("sources.smart_contracts".to_string()).is_err());
+
+      tmp_dir.close()?;
+      Ok(())
+    }
+
+    #[test]
+    fn it_should_ignore_options_that_do_not_exist() -> Result<(), Box<dyn std::error::Error>> {
+      let (tmp_dir, config) = create_test_config(COMMENTED_CONFIG)?;
+
+      config.remove("compiler.unknown".to_string())?;
+      config.remove("unknown.option".to_string())?;
+      assert_eq!(fs::read_to_string(&config.config_file)?, COMMENTED_CONFIG);
+
+      tmp_dir.close()?;
+      Ok(())
+    }
+  }
+
+  mod flatten {
+
+    use super::super::flatten;
+
+    #[test]
+    fn it_should_flatten_nested_tables_into_dotted_keys() {
+      let config: toml::Value = toml::from_str("
+        [sources]
+          artifacts = \"artifacts\"
+          smart_contracts = [\"contracts/*.sol\"]
+        [[deployment.smart_contracts]]
+          name = \"A\"
+          args = [
+            { value = \"1\", kind = \"uint\" }
+          ]
+      ").unwrap();
+
+      let mut entries = vec![];
+      flatten(None, &config, &mut entries);
+
+      let keys = entries.iter().map(|(key, _value)| key.as_str()).collect::<Vec<&str>>();
+      assert_eq!(keys, vec![
+        "deployment.smart_contracts.[0].args.[0].kind",
+        "deployment.smart_contracts.[0].args.[0].value",
+        "deployment.smart_contracts.[0].name",
+        "sources.artifacts",
+        "sources.smart_contracts",
+      ]);
+      assert_eq!(entries[4].1, toml::Value::Array(vec![toml::Value::String("contracts/*.sol".to_string())]));
+    }
+  }
+
+  mod with_environment {
+
+    use crate::config::ProjectConfig;
+
+    fn project_config_from_string(config: &str) -> Result<ProjectConfig, toml::de::Error> {
+      toml::from_str(config)
+    }
+
+    const CONFIG: &str = "
+      [sources]
+        artifacts = \"artifacts\"
+        smart_contracts = [\"contracts/*.sol\"]
+        exclude = [\"contracts/vendor/**\"]
+      [blockchain.connector]
+        protocol = \"rpc\"
+        host = \"localhost\"
+        port = \"8545\"
+      [deployment]
+        gas_limit = 1000
+        [[deployment.smart_contracts]]
+          name = \"A\"
+        [[deployment.smart_contracts]]
+          name = \"B\"
+      [environments.staging.sources]
+        artifacts = \"build/staging\"
+        exclude = [\"contracts/mocks/**\"]
+      [environments.staging.blockchain.connector]
+        protocol = \"ws\"
+        host = \"staging.example.com\"
+        port = \"8546\"
+      [environments.staging.deployment]
+        gas_price = 20
+        [[environments.staging.deployment.smart_contracts]]
+          name = \"A\"
+          address = \"0x00a329c0648769a73afac7f9381e08fb43dbea72\"
+    ";
+
+    #[test]
+    fn it_should_return_base_config_for_undefined_default_environment() {
+      let project_config = project_config_from_string(CONFIG).unwrap().with_environment("development").unwrap();
+      let connector = project_config.blockchain.unwrap().connector.unwrap();
+      assert_eq!(connector.host, "localhost");
+      assert_eq!(project_config.deployment.unwrap().gas_price, None);
+    }
+
+    #[test]
+    fn it_should_fail_for_undefined_environment() {
+      let project_config = project_config_from_string(CONFIG).unwrap();
+      assert!(project_config.with_environment("production").is_err());
+    }
+
+    #[test]
+    fn it_should_override_blockchain_connector() {
+      let project_config = project_config_from_string(CONFIG).unwrap().with_environment("staging").unwrap();
+      let connector = project_config.blockchain.unwrap().connector.unwrap();
+      assert_eq!(connector.protocol, "ws");
+      assert_eq!(connector.host, "staging.example.com");
+      assert_eq!(connector.port, "8546");
+    }
+
+    #[test]
+    fn it_should_add_exclude_patterns_of_sources() {
+      let project_config = project_config_from_string(CONFIG).unwrap().with_environment("staging").unwrap();
+      assert_eq!(project_config.sources.artifacts, "build/staging");
+      assert_eq!(project_config.sources.smart_contracts, vec!["contracts/*.sol"]);
+      assert_eq!(project_config.sources.exclude, Some(vec!["contracts/vendor/**".to_string(), "contracts/mocks/**".to_string()]));
+    }
+
+    #[test]
+    fn it_should_merge_deployment_settings() {
+      let project_config = project_config_from_string(CONFIG).unwrap().with_environment("staging").unwrap();
+      let deployment = project_config.deployment.unwrap();
+      assert_eq!(deployment.gas_price, Some(20));
+      assert_eq!(deployment.gas_limit, Some(1000));
+      assert_eq!(deployment.smart_contracts.len(), 2);
+      assert_eq!(deployment.smart_contracts[0].address, Some("0x00a329c0648769a73afac7f9381e08fb43dbea72".to_string()));
+      assert_eq!(deployment.smart_contracts[1].address, None);
+    }
+  }
+}
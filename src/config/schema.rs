@@ -0,0 +1,266 @@
+use super::error::ConfigError;
+
+use std::fmt;
+use toml;
+
+const SMART_CONTRACT_ARG_KEYS: [&str; 2] = ["value", "kind"];
+
+/// Describes the type of a value in a Vibranium project configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueKind {
+  String,
+  Integer,
+  Boolean,
+  Array(Box<ValueKind>),
+  /// A table with a fixed set of keys.
+  Table(Vec<(&'static str, ValueKind)>),
+  /// A table with arbitrary keys that all share the same kind of value.
+  Map(Box<ValueKind>),
+}
+
+impl ValueKind {
+  /// Returns the schema of `ProjectConfig`.
+  pub fn project_config() -> ValueKind {
+    ValueKind::Table(vec![
+      ("version", ValueKind::Integer),
+      ("extends", ValueKind::String),
+      ("sources", sources_config()),
+      ("compiler", cmd_execution_config()),
+      ("blockchain", blockchain_config()),
+      ("deployment", deployment_config()),
+      ("environments", ValueKind::Map(Box::new(ValueKind::Table(vec![
+        ("sources", sources_config()),
+        ("blockchain", blockchain_config()),
+        ("deployment", deployment_config()),
+      ])))),
+    ])
+  }
+
+  /// Looks up the kind of the value for a dotted configuration option, such as
+  /// `deployment.smart_contracts.[0].gas_limit`.
+  pub fn lookup(&self, option: &str) -> Option<&ValueKind> {
+    option.split('.').try_fold(self, |kind, segment| {
+      match kind {
+        ValueKind::Table(fields) => fields.iter().find(|(name, _)| *name == segment).map(|(_, kind)| kind),
+        ValueKind::Map(kind) => Some(&**kind),
+        ValueKind::Array(kind) if super::parse_index(segment).is_some() => Some(&**kind),
+        _ => None,
+      }
+    })
+  }
+
+  /// Parses a raw value given on the command line into a TOML value of this kind.
+  pub fn parse(&self, value: &str) -> Result<toml::Value, String> {
+    let value = value.trim();
+    match self {
+      ValueKind::String => Ok(toml::Value::String(unquote(value).to_string())),
+      ValueKind::Integer => value.parse::<i64>().map(toml::Value::Integer).map_err(|err| err.to_string()),
+      ValueKind::Boolean => value.parse::<bool>().map(toml::Value::Boolean).map_err(|err| err.to_string()),
+      ValueKind::Array(kind) => {
+        if !is_multi_value_arg(value) {
+          return Err("missing `[` and `]` delimiters".to_string());
+        }
+        split_multi_value_arg(&value[1..value.len() - 1])
+          .iter()
+          .map(|item| kind.parse(item))
+          .collect::<Result<Vec<toml::Value>, String>>()
+          .map(toml::Value::Array)
+      },
+      ValueKind::Table(_) | ValueKind::Map(_) => {
+        toml::from_str::<toml::Value>(&format!("value = {}", value))
+          .map_err(|err| err.to_string())
+          .and_then(|document| {
+            match document.get("value") {
+              Some(table @ toml::Value::Table(_)) => Ok(table.clone()),
+              _ => Err("not an inline table".to_string()),
+            }
+          })
+      },
+    }
+  }
+}
+
+impl fmt::Display for ValueKind {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ValueKind::String => write!(f, "string"),
+      ValueKind::Integer => write!(f, "integer"),
+      ValueKind::Boolean => write!(f, "boolean (`true` or `false`)"),
+      ValueKind::Array(kind) => write!(f, "array of {} (e.g. `[a, b]`)", kind),
+      ValueKind::Table(fields) => {
+        let keys = fields.iter().map(|(name, _)| format!("`{}`", name)).collect::<Vec<String>>();
+        write!(f, "inline table with keys {}", keys.join(", "))
+      },
+      ValueKind::Map(_) => write!(f, "inline table"),
+    }
+  }
+}
+
+/// Parses a raw configuration value given on the command line according to the schema
+/// of `ProjectConfig`. Values of options that aren't part of the schema are parsed as
+/// string, or array of strings when wrapped in `[` and `]`.
+pub fn parse_value(option: &str, value: &str) -> Result<toml::Value, ConfigError> {
+  let schema = ValueKind::project_config();
+  let kind = match schema.lookup(option) {
+    Some(kind) => kind.clone(),
+    None if is_multi_value_arg(value.trim()) => ValueKind::Array(Box::new(ValueKind::String)),
+    None => ValueKind::String,
+  };
+
+  kind.parse(value).map_err(|message| {
+    ConfigError::InvalidValue(option.to_string(), kind.to_string(), format!("{:?} ({})", value, message))
+  })
+}
+
+fn sources_config() -> ValueKind {
+  ValueKind::Table(vec![
+    ("artifacts", ValueKind::String),
+    ("smart_contracts", ValueKind::Array(Box::new(ValueKind::String))),
+    ("exclude", ValueKind::Array(Box::new(ValueKind::String))),
+  ])
+}
+
+fn cmd_execution_config() -> ValueKind {
+  ValueKind::Table(vec![
+    ("cmd", ValueKind::String),
+    ("options", ValueKind::Array(Box::new(ValueKind::String))),
+    ("version", ValueKind::String),
+    ("remappings", ValueKind::Array(Box::new(ValueKind::String))),
+    ("max_contract_size", ValueKind::Integer),
+    ("max_initcode_size", ValueKind::Integer),
+    ("fail_on_size_limit", ValueKind::Boolean),
+  ])
+}
+
+fn blockchain_config() -> ValueKind {
+  ValueKind::Table(vec![
+    ("cmd", ValueKind::String),
+    ("options", ValueKind::Array(Box::new(ValueKind::String))),
+    ("connector", ValueKind::Table(vec![
+      ("protocol", ValueKind::String),
+      ("host", ValueKind::String),
+      ("port", ValueKind::String),
+    ])),
+  ])
+}
+
+fn deployment_config() -> ValueKind {
+  ValueKind::Table(vec![
+    ("tx_confirmations", ValueKind::Integer),
+    ("gas_price", ValueKind::Integer),
+    ("gas_limit", ValueKind::Integer),
+    ("tracking_enabled", ValueKind::Boolean),
+    ("smart_contracts", ValueKind::Array(Box::new(smart_contract_config()))),
+  ])
+}
+
+fn smart_contract_config() -> ValueKind {
+  ValueKind::Table(vec![
+    ("name", ValueKind::String),
+    ("address", ValueKind::String),
+    ("args", ValueKind::Array(Box::new(ValueKind::Table(
+      SMART_CONTRACT_ARG_KEYS.iter().map(|key| (*key, ValueKind::String)).collect()
+    )))),
+    ("gas_price", ValueKind::Integer),
+    ("gas_limit", ValueKind::Integer),
+    ("instance_of", ValueKind::String),
+    ("abi_path", ValueKind::String),
+    ("bytecode_path", ValueKind::String),
+  ])
+}
+
+fn unquote(value: &str) -> &str {
+  let is_quoted = value.len() >= 2 && (
+    (value.starts_with('"') && value.ends_with('"')) || (value.starts_with('\'') && value.ends_with('\''))
+  );
+  if is_quoted {
+    &value[1..value.len() - 1]
+  } else {
+    value
+  }
+}
+
+fn is_multi_value_arg(value: &str) -> bool {
+  value.starts_with('[') && value.ends_with(']')
+}
+
+// Splits the items of a multi value argument on top-level commas, so that commas
+// inside of inline tables, nested arrays or quoted strings are preserved.
+// Empty items are removed.
+fn split_multi_value_arg(value: &str) -> Vec<String> {
+  let mut items = vec![];
+  let mut current = String::new();
+  let mut depth = 0;
+  let mut quote = None;
+
+  for c in value.chars() {
+    match (c, quote) {
+      ('"', None) | ('\'', None) => quote = Some(c),
+      (_, Some(q)) if c == q => quote = None,
+      ('{', None) | ('[', None) => depth += 1,
+      ('}', None) | (']', None) => depth -= 1,
+      (',', None) if depth == 0 => {
+        items.push(current.trim().to_string());
+        current.clear();
+        continue;
+      },
+      _ => (),
+    }
+    current.push(c);
+  }
+  items.push(current.trim().to_string());
+  items.into_iter().filter(|item| !item.is_empty()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+  mod parse_value {
+
+    use super::super::parse_value;
+    use toml::Value;
+
+    #[test]
+    fn it_should_parse_integers() {
+      assert_eq!(parse_value("deployment.gas_limit", "6000000").unwrap(), Value::Integer(6_000_000));
+      assert_eq!(parse_value("environments.staging.deployment.tx_confirmations", "2").unwrap(), Value::Integer(2));
+    }
+
+    #[test]
+    fn it_should_parse_booleans() {
+      assert_eq!(parse_value("deployment.tracking_enabled", "false").unwrap(), Value::Boolean(false));
+    }
+
+    #[test]
+    fn it_should_parse_arrays_of_strings() {
+      assert_eq!(
+        parse_value("compiler.options", "[--foo, \"--bar\", ]").unwrap(),
+        Value::Array(vec![Value::String("--foo".to_string()), Value::String("--bar".to_string())])
+      );
+    }
+
+    #[test]
+    fn it_should_parse_inline_tables_of_smart_contract_args() {
+      let value = parse_value("deployment.smart_contracts.[0].args", "[{ value = \"$A, B\", kind = \"address\" }, { value = \"1\", kind = \"uint\" }]").unwrap();
+      let args = value.as_array().unwrap();
+      assert_eq!(args.len(), 2);
+      assert_eq!(args[0]["value"], Value::String("$A, B".to_string()));
+      assert_eq!(args[1]["kind"], Value::String("uint".to_string()));
+    }
+
+    #[test]
+    fn it_should_keep_values_of_unknown_options_as_strings() {
+      assert_eq!(parse_value("unknown", "42").unwrap(), Value::String("42".to_string()));
+    }
+
+    #[test]
+    fn it_should_name_expected_type_in_error() {
+      let error = parse_value("deployment.gas_limit", "a lot").unwrap_err().to_string();
+      assert!(error.contains("deployment.gas_limit"));
+      assert!(error.contains("expected integer"));
+
+      let error = parse_value("compiler.options", "single-value").unwrap_err().to_string();
+      assert!(error.contains("expected array of string"));
+    }
+  }
+}
@@ -0,0 +1,528 @@
+use super::schema::ValueKind;
+use super::migrations::{CONFIG_VERSION, VERSION_KEY};
+use crate::blockchain::connector::SupportedProtocols;
+
+use ethabi::param_type::Reader;
+use std::fmt;
+use std::ops::Range;
+use std::collections::HashSet;
+use toml;
+use toml_edit::{ImDocument, Item, Table, TableLike, Value};
+
+const INTERPOLATION_START: &str = "${";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticSeverity {
+  Error,
+  /// Problems that don't prevent the configuration from being used, e.g. unknown keys.
+  Warning,
+}
+
+impl fmt::Display for DiagnosticSeverity {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      DiagnosticSeverity::Error => write!(f, "error"),
+      DiagnosticSeverity::Warning => write!(f, "warning"),
+    }
+  }
+}
+
+/// A problem found in a project configuration, located by its dotted option
+/// and the line in the configuration file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigDiagnostic {
+  pub severity: DiagnosticSeverity,
+  pub option: String,
+  pub line: Option<usize>,
+  pub message: String,
+}
+
+impl ConfigDiagnostic {
+  pub fn is_error(&self) -> bool {
+    self.severity == DiagnosticSeverity::Error
+  }
+}
+
+impl fmt::Display for ConfigDiagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if let Some(line) = self.line {
+      write!(f, "line {}: ", line)?;
+    }
+    if !self.option.is_empty() {
+      write!(f, "{}: ", self.option)?;
+    }
+    write!(f, "{}", self.message)
+  }
+}
+
+/// Validates the contents of a configuration file against the schema of `ProjectConfig`
+/// and checks the semantics of the blockchain and deployment configuration. All problems
+/// are returned, ordered by line. Unknown keys are reported as warnings, everything else
+/// as errors.
+///
+/// `base` is the resolved configuration the file `extends`, which can provide required
+/// options and Smart Contracts that are referenced.
+pub fn validate(source: &str, base: Option<&toml::Value>) -> Vec<ConfigDiagnostic> {
+  let document = match ImDocument::parse(source) {
+    Ok(document) => document,
+    Err(err) => {
+      return vec![ConfigDiagnostic {
+        severity: DiagnosticSeverity::Error,
+        option: String::new(),
+        line: err.span().map(|span| line_of(source, span.start)),
+        message: err.message().to_string(),
+      }];
+    }
+  };
+
+  let mut validator = Validator { source, diagnostics: vec![] };
+  let root = Node::Item(document.as_item());
+
+  validator.check_kind(root, &ValueKind::project_config(), "", None);
+  validator.check_version(root);
+  validator.check_sources(root, base.and_then(|base| base.get("sources")));
+
+  if let Some(blockchain) = root.get("blockchain") {
+    validator.check_blockchain(blockchain, "blockchain");
+  }
+
+  let smart_contracts = root.get("deployment").and_then(|deployment| deployment.get("smart_contracts"));
+  // Arrays aren't merged with the extended configuration, so its Smart Contracts
+  // are only deployed if they aren't replaced by this file.
+  let base_names = match smart_contracts {
+    Some(smart_contracts) => contract_names(smart_contracts),
+    None => base
+      .and_then(|base| base.get("deployment"))
+      .and_then(|deployment| deployment.get("smart_contracts"))
+      .and_then(toml::Value::as_array)
+      .map(|smart_contracts| {
+        smart_contracts.iter()
+          .filter_map(|smart_contract| smart_contract.get("name").and_then(toml::Value::as_str).map(str::to_string))
+          .collect()
+      })
+      .unwrap_or_default(),
+  };
+
+  if let Some(smart_contracts) = smart_contracts {
+    validator.check_smart_contracts(smart_contracts, "deployment.smart_contracts", &HashSet::new());
+  }
+
+  let environments = root.get("environments").and_then(|environments| environments.as_table_like());
+
+  for (name, environment) in environments.into_iter().flat_map(|environments| environments.iter()) {
+    let environment = Node::Item(environment);
+    let option = format!("environments.{}", name);
+
+    if let Some(blockchain) = environment.get("blockchain") {
+      validator.check_blockchain(blockchain, &format!("{}.blockchain", option));
+    }
+    if let Some(smart_contracts) = environment.get("deployment").and_then(|deployment| deployment.get("smart_contracts")) {
+      validator.check_smart_contracts(smart_contracts, &format!("{}.deployment.smart_contracts", option), &base_names);
+    }
+  }
+
+  let mut diagnostics = validator.diagnostics;
+  diagnostics.sort_by_key(|diagnostic| diagnostic.line.unwrap_or(usize::MAX));
+  diagnostics
+}
+
+// Parsed TOML documents store values in different containers depending on how they
+// are written, e.g. `[[deployment.smart_contracts]]` vs. inline arrays of tables.
+#[derive(Clone, Copy)]
+enum Node<'a> {
+  Item(&'a Item),
+  Value(&'a Value),
+  Table(&'a Table),
+}
+
+impl<'a> Node<'a> {
+  fn span(self) -> Option<Range<usize>> {
+    match self {
+      Node::Item(item) => item.span(),
+      Node::Value(value) => value.span(),
+      Node::Table(table) => table.span(),
+    }
+  }
+
+  fn type_name(self) -> &'static str {
+    match self {
+      Node::Item(item) => item.type_name(),
+      Node::Value(value) => value.type_name(),
+      Node::Table(_table) => "table",
+    }
+  }
+
+  fn as_table_like(self) -> Option<&'a dyn TableLike> {
+    match self {
+      Node::Item(item) => item.as_table_like(),
+      Node::Value(value) => value.as_inline_table().map(|table| table as &dyn TableLike),
+      Node::Table(table) => Some(table),
+    }
+  }
+
+  fn elements(self) -> Option<Vec<Node<'a>>> {
+    match self {
+      Node::Item(Item::ArrayOfTables(tables)) => Some(tables.iter().map(Node::Table).collect()),
+      Node::Item(Item::Value(value)) | Node::Value(value) => value.as_array().map(|array| array.iter().map(Node::Value).collect()),
+      _ => None,
+    }
+  }
+
+  fn get(self, key: &str) -> Option<Node<'a>> {
+    self.as_table_like().and_then(|table| table.get(key)).map(Node::Item)
+  }
+
+  fn get_str(self, key: &str) -> Option<&'a str> {
+    self.get(key).and_then(|node| node.as_str())
+  }
+
+  fn key_span(self, key: &str) -> Option<Range<usize>> {
+    self.as_table_like()
+      .and_then(|table| table.get_key_value(key))
+      .and_then(|(key, item)| key.span().or_else(|| item.span()))
+      .or_else(|| self.span())
+  }
+
+  fn as_str(self) -> Option<&'a str> {
+    match self {
+      Node::Item(item) => item.as_str(),
+      Node::Value(value) => value.as_str(),
+      Node::Table(_table) => None,
+    }
+  }
+
+  fn as_integer(self) -> Option<i64> {
+    match self {
+      Node::Item(item) => item.as_integer(),
+      Node::Value(value) => value.as_integer(),
+      Node::Table(_table) => None,
+    }
+  }
+
+  fn as_bool(self) -> Option<bool> {
+    match self {
+      Node::Item(item) => item.as_bool(),
+      Node::Value(value) => value.as_bool(),
+      Node::Table(_table) => None,
+    }
+  }
+}
+
+struct Validator<'a> {
+  source: &'a str,
+  diagnostics: Vec<ConfigDiagnostic>,
+}
+
+impl<'a> Validator<'a> {
+  fn report(&mut self, option: &str, span: Option<Range<usize>>, message: String) {
+    self.push(DiagnosticSeverity::Error, option, span, message);
+  }
+
+  fn warn(&mut self, option: &str, span: Option<Range<usize>>, message: String) {
+    self.push(DiagnosticSeverity::Warning, option, span, message);
+  }
+
+  fn push(&mut self, severity: DiagnosticSeverity, option: &str, span: Option<Range<usize>>, message: String) {
+    self.diagnostics.push(ConfigDiagnostic {
+      severity,
+      option: option.to_string(),
+      line: span.map(|span| line_of(self.source, span.start)),
+      message,
+    });
+  }
+
+  fn check_kind(&mut self, node: Node, kind: &ValueKind, option: &str, span: Option<Range<usize>>) {
+    let span = span.or_else(|| node.span());
+    match kind {
+      ValueKind::Table(_) | ValueKind::Map(_) => {
+        let table = match node.as_table_like() {
+          Some(table) => table,
+          None => return self.report(option, span, format!("expected table but got {}", node.type_name())),
+        };
+        for (key, item) in table.iter() {
+          let child_option = if option.is_empty() { key.to_string() } else { format!("{}.{}", option, key) };
+          let child_span = node.key_span(key);
+          let child_kind = match kind {
+            ValueKind::Table(fields) => fields.iter().find(|(name, _)| *name == key).map(|(_, kind)| kind),
+            ValueKind::Map(kind) => Some(&**kind),
+            _ => None,
+          };
+          match child_kind {
+            Some(child_kind) => self.check_kind(Node::Item(item), child_kind, &child_option, child_span),
+            None => self.warn(&child_option, child_span, "unknown key".to_string()),
+          }
+        }
+      },
+      ValueKind::Array(kind) => {
+        match node.elements() {
+          Some(elements) => {
+            for (index, element) in elements.into_iter().enumerate() {
+              self.check_kind(element, kind, &format!("{}.[{}]", option, index), None);
+            }
+          },
+          None => self.report(option, span, format!("expected array but got {}", node.type_name())),
+        }
+      },
+      ValueKind::String if node.as_str().is_none() => {
+        self.report(option, span, format!("expected string but got {}", node.type_name()));
+      },
+      ValueKind::Integer => {
+        match node.as_integer() {
+          Some(value) if value < 0 => self.report(option, span, format!("expected non-negative integer but got {}", value)),
+          Some(_value) => (),
+          None => self.report(option, span, format!("expected integer but got {}", node.type_name())),
+        }
+      },
+      ValueKind::Boolean if node.as_bool().is_none() => {
+        self.report(option, span, format!("expected boolean but got {}", node.type_name()));
+      },
+      _ => (),
+    }
+  }
+
+  fn check_version(&mut self, root: Node) {
+    let version = root.get(VERSION_KEY).and_then(|version| version.as_integer());
+    if let Some(version) = version.filter(|version| *version > CONFIG_VERSION) {
+      self.report(VERSION_KEY, root.key_span(VERSION_KEY), format!("unsupported version {} (latest supported version is {})", version, CONFIG_VERSION));
+    }
+  }
+
+  fn check_sources(&mut self, root: Node, base_sources: Option<&toml::Value>) {
+    let sources = match root.get("sources") {
+      Some(sources) => sources,
+      None if base_sources.is_some() => return,
+      None => return self.report("sources", None, "missing required table".to_string()),
+    };
+    for key in &["artifacts", "smart_contracts"] {
+      if sources.get(key).is_none() && base_sources.and_then(|base_sources| base_sources.get(key)).is_none() {
+        self.report(&format!("sources.{}", key), sources.span(), "missing required key".to_string());
+      }
+    }
+  }
+
+  fn check_blockchain(&mut self, blockchain: Node, option: &str) {
+    let connector = match blockchain.get("connector") {
+      Some(connector) => connector,
+      None => return,
+    };
+    if let Some(protocol) = connector.get_str("protocol") {
+      if !is_interpolated(protocol) && protocol.parse::<SupportedProtocols>().is_err() {
+        self.report(
+          &format!("{}.connector.protocol", option),
+          connector.key_span("protocol"),
+          format!("unsupported protocol '{}' (expected `{}` or `{}`)", protocol, SupportedProtocols::Rpc.to_string(), SupportedProtocols::Ws.to_string())
+        );
+      }
+    }
+  }
+
+  // Smart Contracts of environments can reference and extend the ones of the
+  // base configuration, which are passed as `base_names`.
+  fn check_smart_contracts(&mut self, smart_contracts: Node, option: &str, base_names: &HashSet<String>) {
+    let smart_contracts = match smart_contracts.elements() {
+      Some(smart_contracts) => smart_contracts,
+      None => return,
+    };
+    let names = smart_contracts.iter().filter_map(|smart_contract| smart_contract.get_str("name")).collect::<HashSet<&str>>();
+    let mut seen_names = HashSet::new();
+
+    for (index, smart_contract) in smart_contracts.into_iter().enumerate() {
+      let contract_option = format!("{}.[{}]", option, index);
+
+      let name = smart_contract.get_str("name");
+      match name {
+        None => self.report(&format!("{}.name", contract_option), smart_contract.span(), "missing required key".to_string()),
+        Some(name) if !seen_names.insert(name) => {
+          self.report(&format!("{}.name", contract_option), smart_contract.key_span("name"), format!("duplicate Smart Contract name '{}'", name));
+        },
+        Some(_name) => (),
+      }
+
+      let extends_base = name.map(|name| base_names.contains(name)).unwrap_or(false);
+      let has_abi_path = smart_contract.get("abi_path").is_some();
+      let has_bytecode_path = smart_contract.get("bytecode_path").is_some();
+
+      if !extends_base && has_abi_path && !has_bytecode_path {
+        self.report(&format!("{}.abi_path", contract_option), smart_contract.key_span("abi_path"), "`abi_path` requires `bytecode_path`".to_string());
+      } else if !extends_base && has_bytecode_path && !has_abi_path {
+        self.report(&format!("{}.bytecode_path", contract_option), smart_contract.key_span("bytecode_path"), "`bytecode_path` requires `abi_path`".to_string());
+      }
+
+      let args = smart_contract.get("args").and_then(|args| args.elements()).unwrap_or_default();
+
+      for (arg_index, arg) in args.into_iter().enumerate() {
+        let arg_option = format!("{}.args.[{}]", contract_option, arg_index);
+
+        for key in &["value", "kind"] {
+          if arg.get(key).is_none() {
+            self.report(&format!("{}.{}", arg_option, key), arg.span(), "missing required key".to_string());
+          }
+        }
+
+        let kind = arg.get_str("kind").filter(|kind| !is_interpolated(kind));
+        if let Some(kind) = kind {
+          if let Err(err) = Reader::read(kind) {
+            self.report(&format!("{}.kind", arg_option), arg.key_span("kind"), format!("invalid kind '{}': {}", kind, err));
+          }
+        }
+
+        if let (Some("address"), Some(value)) = (kind, arg.get_str("value")) {
+          if value.starts_with('$') && !is_interpolated(value) {
+            let reference = &value[1..];
+            if !names.contains(reference) && !base_names.contains(reference) {
+              self.report(&format!("{}.value", arg_option), arg.key_span("value"), format!("reference to undefined Smart Contract '{}'", reference));
+            }
+          }
+        }
+      }
+    }
+  }
+}
+
+fn contract_names(smart_contracts: Node) -> HashSet<String> {
+  smart_contracts.elements()
+    .unwrap_or_default()
+    .into_iter()
+    .filter_map(|smart_contract| smart_contract.get_str("name").map(str::to_string))
+    .collect()
+}
+
+fn is_interpolated(value: &str) -> bool {
+  value.contains(INTERPOLATION_START)
+}
+
+fn line_of(source: &str, offset: usize) -> usize {
+  source[..offset.min(source.len())].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::{validate, DiagnosticSeverity};
+  use crate::config::CONFIG_VERSION;
+
+  const VALID_CONFIG: &str = "[sources]
+artifacts = \"artifacts\"
+smart_contracts = [\"contracts/*.sol\"]
+
+[blockchain.connector]
+protocol = \"rpc\"
+
+[[deployment.smart_contracts]]
+name = \"Token\"
+args = [{ value = \"100\", kind = \"uint\" }]
+
+[[deployment.smart_contracts]]
+name = \"Exchange\"
+args = [{ value = \"$Token\", kind = \"address\" }]
+";
+
+  fn messages(source: &str) -> Vec<String> {
+    validate(source, None).into_iter().map(|diagnostic| diagnostic.to_string()).collect()
+  }
+
+  #[test]
+  fn it_should_accept_valid_config() {
+    assert!(validate(VALID_CONFIG, None).is_empty());
+  }
+
+  #[test]
+  fn it_should_report_syntax_errors_with_line() {
+    let diagnostics = validate("[sources]\nartifacts = \n", None);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].line, Some(2));
+  }
+
+  #[test]
+  fn it_should_report_unknown_keys_and_wrong_types() {
+    let source = VALID_CONFIG.replace("protocol = \"rpc\"", "protocl = \"rpc\"\nport = 8545");
+    assert_eq!(messages(&source), vec![
+      "line 6: blockchain.connector.protocl: unknown key",
+      "line 7: blockchain.connector.port: expected string but got integer",
+    ]);
+    let severities = validate(&source, None).into_iter().map(|diagnostic| diagnostic.severity).collect::<Vec<_>>();
+    assert_eq!(severities, vec![DiagnosticSeverity::Warning, DiagnosticSeverity::Error]);
+  }
+
+  #[test]
+  fn it_should_report_missing_required_keys() {
+    assert_eq!(messages("[sources]\nartifacts = \"artifacts\"\n"), vec![
+      "line 1: sources.smart_contracts: missing required key",
+    ]);
+  }
+
+  #[test]
+  fn it_should_report_unsupported_versions() {
+    let source = format!("version = {}\n{}", CONFIG_VERSION + 1, VALID_CONFIG);
+    assert_eq!(messages(&source), vec![
+      format!("line 1: version: unsupported version {} (latest supported version is {})", CONFIG_VERSION + 1, CONFIG_VERSION),
+    ]);
+  }
+
+  #[test]
+  fn it_should_report_unsupported_protocols() {
+    let source = VALID_CONFIG.replace("protocol = \"rpc\"", "protocol = \"ipc\"");
+    assert_eq!(messages(&source), vec![
+      "line 6: blockchain.connector.protocol: unsupported protocol 'ipc' (expected `rpc` or `ws`)",
+    ]);
+  }
+
+  #[test]
+  fn it_should_report_invalid_kinds_and_undefined_references() {
+    let source = VALID_CONFIG
+      .replace("kind = \"uint\"", "kind = \"uint257x\"")
+      .replace("$Token", "$Tokn");
+    let diagnostics = validate(&source, None);
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].option, "deployment.smart_contracts.[0].args.[0].kind");
+    assert_eq!(diagnostics[0].line, Some(10));
+    assert_eq!(diagnostics[1].to_string(), "line 14: deployment.smart_contracts.[1].args.[0].value: reference to undefined Smart Contract 'Tokn'");
+  }
+
+  #[test]
+  fn it_should_report_abi_path_without_bytecode_path_and_duplicate_names() {
+    let source = VALID_CONFIG.replace("name = \"Exchange\"", "name = \"Token\"\nabi_path = \"Token.abi\"");
+    assert_eq!(messages(&source), vec![
+      "line 13: deployment.smart_contracts.[1].name: duplicate Smart Contract name 'Token'",
+      "line 14: deployment.smart_contracts.[1].abi_path: `abi_path` requires `bytecode_path`",
+    ]);
+  }
+
+  #[test]
+  fn it_should_check_environments_against_base_smart_contracts() {
+    let source = format!("{}
+[[environments.staging.deployment.smart_contracts]]
+name = \"Exchange\"
+args = [{{ value = \"$Token\", kind = \"address\" }}, {{ value = \"$Other\", kind = \"address\" }}]
+", VALID_CONFIG);
+    assert_eq!(messages(&source), vec![
+      "line 18: environments.staging.deployment.smart_contracts.[0].args.[1].value: reference to undefined Smart Contract 'Other'",
+    ]);
+  }
+
+  #[test]
+  fn it_should_take_extended_config_into_account() {
+    let base: toml::Value = toml::from_str("
+      [sources]
+        artifacts = \"artifacts\"
+        smart_contracts = [\"contracts/*.sol\"]
+
+      [[deployment.smart_contracts]]
+        name = \"Token\"
+    ").unwrap();
+    let source = "extends = \"../base.toml\"
+
+[[environments.staging.deployment.smart_contracts]]
+name = \"Exchange\"
+args = [{ value = \"$Token\", kind = \"address\" }]
+";
+    assert!(validate(source, Some(&base)).is_empty());
+    assert_eq!(validate(source, None).len(), 2);
+  }
+
+  #[test]
+  fn it_should_skip_semantic_checks_of_interpolated_values() {
+    let source = VALID_CONFIG.replace("protocol = \"rpc\"", "protocol = \"${PROTOCOL:-rpc}\"");
+    assert!(validate(&source, None).is_empty());
+  }
+}
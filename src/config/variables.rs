@@ -0,0 +1,221 @@
+use super::error::ConfigError;
+use super::schema;
+
+use regex::{Captures, Regex};
+use toml;
+use toml_query::set::TomlValueSetExt;
+use toml_query::insert::TomlValueInsertExt;
+use toml_query::error::Error::IdentifierNotFoundInDocument;
+
+const OVERRIDE_PREFIX: &str = "VIBRANIUM_";
+const OVERRIDE_SEPARATOR: &str = "__";
+
+lazy_static! {
+  static ref VARIABLE_EXPRESSION: Regex = Regex::new(r"\$?\$\{([A-Za-z_][A-Za-z0-9_]*)(:-([^}]*))?\}").unwrap();
+}
+
+/// Replaces `${VAR}` and `${VAR:-default}` in all string values of the given document
+/// with the value of the variable returned by `lookup`. The default is used when the
+/// variable is unset or empty. `$${` can be used to write a literal `${`.
+pub fn interpolate<F>(value: &mut toml::Value, lookup: &F) -> Result<(), ConfigError> where F: Fn(&str) -> Option<String> {
+  match value {
+    toml::Value::String(string) => {
+      *string = interpolate_str(string, lookup)?;
+      Ok(())
+    },
+    toml::Value::Array(values) => values.iter_mut().try_for_each(|value| interpolate(value, lookup)),
+    toml::Value::Table(table) => table.iter_mut().try_for_each(|(_key, value)| interpolate(value, lookup)),
+    _ => Ok(()),
+  }
+}
+
+fn interpolate_str<F>(string: &str, lookup: &F) -> Result<String, ConfigError> where F: Fn(&str) -> Option<String> {
+  let mut result = String::new();
+  let mut last_match = 0;
+
+  for captures in VARIABLE_EXPRESSION.captures_iter(string) {
+    let expression = captures.get(0).unwrap();
+    result.push_str(&string[last_match..expression.start()]);
+    result.push_str(&resolve_expression(&captures, lookup)?);
+    last_match = expression.end();
+  }
+
+  result.push_str(&string[last_match..]);
+  Ok(result)
+}
+
+fn resolve_expression<F>(captures: &Captures, lookup: &F) -> Result<String, ConfigError> where F: Fn(&str) -> Option<String> {
+  let expression = &captures[0];
+
+  if expression.starts_with("$$") {
+    return Ok(expression[1..].to_string());
+  }
+
+  let name = &captures[1];
+  match (lookup(name).filter(|value| !value.is_empty()), captures.get(3)) {
+    (Some(value), _) => Ok(value),
+    (None, Some(default)) => Ok(default.as_str().to_string()),
+    (None, None) => Err(ConfigError::UndefinedVariable(name.to_string())),
+  }
+}
+
+/// Collects configuration overrides from `VIBRANIUM_<SECTION>__<KEY>` variables.
+/// Segments are separated by double underscores, so `VIBRANIUM_DEPLOYMENT__GAS_LIMIT`
+/// overrides `deployment.gas_limit` and `VIBRANIUM_DEPLOYMENT__SMART_CONTRACTS__0__ADDRESS`
+/// overrides `deployment.smart_contracts.[0].address`.
+pub fn overrides<I>(variables: I) -> Result<Vec<(String, toml::Value)>, ConfigError> where I: Iterator<Item = (String, String)> {
+  let mut overrides = variables
+    .filter(|(name, _value)| name.starts_with(OVERRIDE_PREFIX) && name.contains(OVERRIDE_SEPARATOR))
+    .map(|(name, value)| {
+      let option = name[OVERRIDE_PREFIX.len()..]
+        .split(OVERRIDE_SEPARATOR)
+        .map(|segment| {
+          match segment.parse::<usize>() {
+            Ok(index) => format!("[{}]", index),
+            Err(_) => segment.to_lowercase(),
+          }
+        })
+        .collect::<Vec<String>>()
+        .join(".");
+      schema::parse_value(&option, &value)
+        .map(|value| (option, value))
+        .map_err(|err| ConfigError::Other(format!("Invalid value in environment variable {}: {}", &name, err)))
+    })
+    .collect::<Result<Vec<(String, toml::Value)>, ConfigError>>()?;
+
+  overrides.sort_by(|a, b| a.0.cmp(&b.0));
+  Ok(overrides)
+}
+
+pub fn apply_override(document: &mut toml::Value, option: &str, value: toml::Value) -> Result<(), ConfigError> {
+  if let Err(err) = document.set(option, value.clone()) {
+    match err {
+      IdentifierNotFoundInDocument(_message) => {
+        document.insert(option, value).map_err(ConfigError::Query)?;
+      },
+      _ => Err(ConfigError::Query(err))?
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+  use std::collections::HashMap;
+
+  fn lookup(name: &str) -> Option<String> {
+    let mut variables = HashMap::new();
+    variables.insert("HOST", "staging.example.com");
+    variables.insert("EMPTY", "");
+    variables.get(name).map(|value| value.to_string())
+  }
+
+  mod interpolate {
+
+    use super::lookup;
+    use super::super::interpolate;
+
+    fn interpolate_str(value: &str) -> Result<String, crate::config::error::ConfigError> {
+      let mut value = toml::Value::String(value.to_string());
+      interpolate(&mut value, &lookup)?;
+      Ok(value.as_str().unwrap().to_string())
+    }
+
+    #[test]
+    fn it_should_replace_variables() {
+      assert_eq!(interpolate_str("${HOST}").unwrap(), "staging.example.com");
+      assert_eq!(interpolate_str("ws://${HOST}:8546").unwrap(), "ws://staging.example.com:8546");
+    }
+
+    #[test]
+    fn it_should_use_defaults_for_unset_or_empty_variables() {
+      assert_eq!(interpolate_str("${PORT:-8545}").unwrap(), "8545");
+      assert_eq!(interpolate_str("${EMPTY:-default}").unwrap(), "default");
+      assert_eq!(interpolate_str("${HOST:-localhost}").unwrap(), "staging.example.com");
+    }
+
+    #[test]
+    fn it_should_fail_for_undefined_variables_without_default() {
+      assert!(interpolate_str("${PORT}").is_err());
+    }
+
+    #[test]
+    fn it_should_leave_smart_contract_references_and_escaped_expressions_untouched() {
+      assert_eq!(interpolate_str("$Token").unwrap(), "$Token");
+      assert_eq!(interpolate_str("$${HOST}").unwrap(), "${HOST}");
+    }
+
+    #[test]
+    fn it_should_replace_variables_in_nested_values() {
+      let mut value: toml::Value = toml::from_str("
+        [[deployment.smart_contracts]]
+          args = [{ value = \"${HOST}\", kind = \"string\" }]
+      ").unwrap();
+      interpolate(&mut value, &lookup).unwrap();
+      assert_eq!(value["deployment"]["smart_contracts"][0]["args"][0]["value"].as_str(), Some("staging.example.com"));
+    }
+  }
+
+  mod overrides {
+
+    use super::super::{overrides, apply_override};
+    use crate::config::ProjectConfig;
+
+    fn variables(variables: Vec<(&str, &str)>) -> impl Iterator<Item = (String, String)> {
+      variables.into_iter().map(|(name, value)| (name.to_string(), value.to_string())).collect::<Vec<(String, String)>>().into_iter()
+    }
+
+    #[test]
+    fn it_should_map_variables_to_typed_config_options() {
+      let overrides = overrides(variables(vec![
+        ("VIBRANIUM_DEPLOYMENT__GAS_LIMIT", "6000000"),
+        ("VIBRANIUM_BLOCKCHAIN__CONNECTOR__HOST", "10.0.0.1"),
+        ("VIBRANIUM_DEPLOYMENT__SMART_CONTRACTS__0__ADDRESS", "0x0"),
+        ("VIBRANIUM_LOG", "ignored"),
+        ("PATH", "ignored"),
+      ])).unwrap();
+
+      assert_eq!(overrides, vec![
+        ("blockchain.connector.host".to_string(), toml::Value::String("10.0.0.1".to_string())),
+        ("deployment.gas_limit".to_string(), toml::Value::Integer(6_000_000)),
+        ("deployment.smart_contracts.[0].address".to_string(), toml::Value::String("0x0".to_string())),
+      ]);
+    }
+
+    #[test]
+    fn it_should_fail_for_values_of_wrong_type() {
+      assert!(overrides(variables(vec![("VIBRANIUM_DEPLOYMENT__GAS_LIMIT", "a lot")])).is_err());
+    }
+
+    #[test]
+    fn it_should_apply_overrides_to_existing_and_missing_options() {
+      let mut document: toml::Value = toml::from_str("
+        [blockchain.connector]
+          host = \"localhost\"
+      ").unwrap();
+
+      apply_override(&mut document, "blockchain.connector.host", toml::Value::String("10.0.0.1".to_string())).unwrap();
+      apply_override(&mut document, "deployment.gas_limit", toml::Value::Integer(10)).unwrap();
+
+      assert_eq!(document["blockchain"]["connector"]["host"].as_str(), Some("10.0.0.1"));
+      assert_eq!(document["deployment"]["gas_limit"].as_integer(), Some(10));
+    }
+
+    #[test]
+    fn it_should_apply_overrides_to_absent_sections() {
+      let mut document: toml::Value = toml::from_str("
+        [sources]
+          artifacts = \"artifacts\"
+          smart_contracts = [\"contracts/*.sol\"]
+      ").unwrap();
+
+      apply_override(&mut document, "deployment.gas_limit", toml::Value::Integer(100)).unwrap();
+
+      let project_config = document.try_into::<ProjectConfig>().unwrap();
+      let deployment_config = project_config.deployment.unwrap();
+      assert_eq!(deployment_config.gas_limit, Some(100));
+      assert!(deployment_config.smart_contracts.is_empty());
+    }
+  }
+}
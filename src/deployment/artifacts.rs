@@ -0,0 +1,237 @@
+use super::error::DeploymentError;
+use super::linker::{self, LinkReferences};
+use crate::compiler::artifact::{self, ContractArtifact};
+use crate::compiler::sizes;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const ARTIFACT_EXTENSION_BINARY: &str = "bin";
+pub const ARTIFACT_EXTENSION_ABI: &str = "abi";
+
+/// Separates the source file from the contract name in qualified references,
+/// e.g. `contracts/Token.sol:Token`.
+pub const SOURCE_SEPARATOR: char = ':';
+
+/// ABI and bytecode of a Smart Contract, read from its JSON artifact or from a `.abi` and `.bin` pair.
+pub struct Artifact {
+  pub path: PathBuf,
+  pub abi: Vec<u8>,
+  /// Hex encoded bytecode, which is unlinked if it uses libraries.
+  pub bytecode: String,
+  pub link_references: LinkReferences,
+  /// Size of the deployed bytecode in bytes, if it's known.
+  pub runtime_size: Option<usize>,
+}
+
+impl Artifact {
+  pub fn new(path: PathBuf, abi: Vec<u8>, bytecode: String) -> Artifact {
+    let link_references = linker::find_link_references(&bytecode, &BTreeMap::new());
+    Artifact { path, abi, bytecode, link_references, runtime_size: None }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ArtifactFiles {
+  Json(PathBuf, Box<ContractArtifact>),
+  Legacy { abi: PathBuf, bin: PathBuf },
+}
+
+/// An artifact of the artifacts directory. Artifacts that only consist of a `.abi` and `.bin`
+/// pair are named after their contract and don't know their source file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedArtifact {
+  pub contract_name: String,
+  pub source_path: Option<String>,
+  files: ArtifactFiles,
+}
+
+impl IndexedArtifact {
+  /// The reference that resolves to this artifact only, `<SourcePath>:<ContractName>`
+  /// if the source file is known.
+  pub fn reference(&self) -> String {
+    match &self.source_path {
+      Some(source_path) => format!("{}{}{}", source_path, SOURCE_SEPARATOR, self.contract_name),
+      None => self.contract_name.clone(),
+    }
+  }
+
+  pub fn load(&self) -> Result<Artifact, DeploymentError> {
+    match &self.files {
+      ArtifactFiles::Json(path, artifact) => {
+        let abi = serde_json::to_vec(&artifact.abi).map_err(|err| DeploymentError::InvalidArtifact(self.reference(), err.to_string()))?;
+        Ok(Artifact {
+          path: path.clone(),
+          abi,
+          bytecode: artifact.bytecode.clone(),
+          link_references: linker::find_link_references(&artifact.bytecode, &artifact.link_references),
+          runtime_size: sizes::bytecode_size(&artifact.deployed_bytecode),
+        })
+      },
+      ArtifactFiles::Legacy { abi, bin } => {
+        if !abi.exists() {
+          return Err(DeploymentError::MissingArtifact(ARTIFACT_EXTENSION_ABI.to_string(), bin.to_string_lossy().to_string()));
+        } else if !bin.exists() {
+          return Err(DeploymentError::MissingArtifact(ARTIFACT_EXTENSION_BINARY.to_string(), abi.to_string_lossy().to_string()));
+        }
+        Ok(Artifact::new(bin.clone(), fs::read(abi)?, fs::read_to_string(bin)?))
+      },
+    }
+  }
+}
+
+/// The artifacts of the artifacts directory by contract name. A `.abi` and `.bin` pair is
+/// only indexed if there's no JSON artifact of the same name.
+#[derive(Debug, Default)]
+pub struct ArtifactIndex {
+  artifacts: BTreeMap<String, Vec<IndexedArtifact>>,
+  artifact_names: BTreeMap<String, IndexedArtifact>,
+}
+
+impl ArtifactIndex {
+  pub fn read(artifacts_dir: &Path) -> Result<ArtifactIndex, DeploymentError> {
+    let mut index = ArtifactIndex::default();
+    if !artifacts_dir.is_dir() {
+      return Ok(index);
+    }
+
+    let named_artifacts = artifact::read_named_artifacts(artifacts_dir).map_err(|err| DeploymentError::Other(err.to_string()))?;
+    for (artifact_name, artifact) in named_artifacts {
+      let path = ContractArtifact::path(artifacts_dir, &artifact_name);
+      index.insert(artifact_name, IndexedArtifact {
+        contract_name: artifact.contract_name.clone(),
+        source_path: Some(artifact.source_path.clone()),
+        files: ArtifactFiles::Json(path, Box::new(artifact)),
+      });
+    }
+
+    let legacy_names = fs::read_dir(artifacts_dir)?
+      .filter_map(Result::ok)
+      .map(|entry| entry.path())
+      .filter(|path| {
+        match path.extension().and_then(|extension| extension.to_str()) {
+          Some(extension) => extension == ARTIFACT_EXTENSION_ABI || extension == ARTIFACT_EXTENSION_BINARY,
+          None => false,
+        }
+      })
+      .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().to_string()))
+      .filter(|name| !index.artifact_names.contains_key(name))
+      .collect::<BTreeSet<String>>();
+
+    for name in legacy_names {
+      index.insert(name.clone(), IndexedArtifact {
+        contract_name: name.clone(),
+        source_path: None,
+        files: ArtifactFiles::Legacy {
+          abi: artifacts_dir.join(format!("{}.{}", name, ARTIFACT_EXTENSION_ABI)),
+          bin: artifacts_dir.join(format!("{}.{}", name, ARTIFACT_EXTENSION_BINARY)),
+        },
+      });
+    }
+    Ok(index)
+  }
+
+  /// Finds the artifact of a contract by its exact name, optionally qualified by its source file,
+  /// e.g. `Token` or `contracts/Token.sol:Token`. Artifact names, like `Token-contracts_Token_sol`,
+  /// are accepted as well. Fails if several artifacts match the reference.
+  pub fn find(&self, reference: &str) -> Result<Option<&IndexedArtifact>, DeploymentError> {
+    let (source_path, contract_name) = match reference.rfind(SOURCE_SEPARATOR) {
+      Some(index) => (Some(normalize_source_path(&reference[..index])), &reference[index + 1..]),
+      None => (None, reference),
+    };
+
+    let candidates = self.artifacts.get(contract_name)
+      .map(|artifacts| {
+        artifacts.iter()
+          .filter(|artifact| source_path.is_none() || artifact.source_path == source_path)
+          .collect::<Vec<&IndexedArtifact>>()
+      })
+      .unwrap_or_default();
+
+    match candidates.len() {
+      0 if source_path.is_none() => Ok(self.artifact_names.get(reference)),
+      0 => Ok(None),
+      1 => Ok(Some(candidates[0])),
+      _ => Err(DeploymentError::AmbiguousArtifact(reference.to_string(), candidates.iter().map(|artifact| artifact.reference()).collect())),
+    }
+  }
+
+  fn insert(&mut self, artifact_name: String, artifact: IndexedArtifact) {
+    self.artifacts.entry(artifact.contract_name.clone()).or_default().push(artifact.clone());
+    self.artifact_names.insert(artifact_name, artifact);
+  }
+}
+
+/// Normalizes a source path as used in references, e.g. `./contracts\Token.sol`, like source
+/// paths of artifacts, which are relative to the project and use `/` as separator.
+pub fn normalize_source_path(source_path: &str) -> String {
+  let source_path = source_path.replace('\\', "/");
+  source_path.trim_start_matches("./").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+
+  extern crate tempfile;
+
+  use std::fs;
+  use tempfile::tempdir;
+  use super::ArtifactIndex;
+  use crate::compiler::artifact::{ArtifactCompiler, ContractArtifact};
+  use crate::deployment::error::DeploymentError;
+
+  fn write_artifact(artifacts_dir: &std::path::Path, artifact_name: &str, name: &str, source_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let artifact = ContractArtifact {
+      contract_name: name.to_string(),
+      source_path: source_path.to_string(),
+      source_hash: "0x".to_string(),
+      compiler: ArtifactCompiler { name: "solc".to_string(), version: None },
+      abi: serde_json::json!([]),
+      bytecode: format!("6080{}", name.len()),
+      deployed_bytecode: String::new(),
+      link_references: Default::default(),
+    };
+    artifact.write(&ContractArtifact::path(artifacts_dir, artifact_name))?;
+    Ok(())
+  }
+
+  #[test]
+  fn it_should_find_artifacts_by_exact_and_qualified_names() -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_dir = tempdir()?;
+    let artifacts_dir = tmp_dir.path();
+    write_artifact(artifacts_dir, "Token-contracts_Token_sol", "Token", "contracts/Token.sol")?;
+    write_artifact(artifacts_dir, "Token-contracts_legacy_Token_sol", "Token", "contracts/legacy/Token.sol")?;
+    write_artifact(artifacts_dir, "TokenSale", "TokenSale", "contracts/TokenSale.sol")?;
+    fs::write(artifacts_dir.join("TokenSale.abi"), "[]")?;
+    fs::write(artifacts_dir.join("TokenSale.bin"), "6080")?;
+    fs::write(artifacts_dir.join("Ownable.abi"), "[]")?;
+    fs::write(artifacts_dir.join("Ownable.bin"), "6040")?;
+    fs::write(artifacts_dir.join("Math.bin"), "6020")?;
+
+    let index = ArtifactIndex::read(artifacts_dir)?;
+    let reference = |reference: &str| index.find(reference).unwrap().map(|artifact| artifact.reference());
+
+    match index.find("Token") {
+      Err(DeploymentError::AmbiguousArtifact(reference, candidates)) => {
+        assert_eq!(reference, "Token");
+        assert_eq!(candidates, vec!["contracts/Token.sol:Token", "contracts/legacy/Token.sol:Token"]);
+      },
+      _ => panic!("Expected ambiguous artifact error"),
+    }
+    assert_eq!(reference("contracts/legacy/Token.sol:Token"), Some("contracts/legacy/Token.sol:Token".to_string()));
+    assert_eq!(reference("./contracts/Token.sol:Token"), Some("contracts/Token.sol:Token".to_string()));
+    assert_eq!(reference("Token-contracts_Token_sol"), Some("contracts/Token.sol:Token".to_string()));
+    assert_eq!(reference("TokenSale"), Some("contracts/TokenSale.sol:TokenSale".to_string()));
+    assert_eq!(reference("Ownable"), Some("Ownable".to_string()));
+    assert_eq!(reference("contracts/Ownable.sol:Ownable"), None);
+    assert_eq!(reference("Tok"), None);
+
+    assert_eq!(index.find("TokenSale")?.unwrap().load()?.bytecode, "60809");
+    assert_eq!(index.find("Ownable")?.unwrap().load()?.bytecode, "6040");
+    assert!(index.find("Math")?.unwrap().load().is_err());
+
+    tmp_dir.close()?;
+    Ok(())
+  }
+}
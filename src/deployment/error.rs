@@ -7,6 +7,7 @@ use toml_query;
 use ethabi;
 
 use crate::blockchain;
+use crate::compiler::sizes::SizeLimitViolation;
 use crate::config;
 
 #[derive(Debug)]
@@ -17,9 +18,14 @@ pub enum DeploymentError {
   NothingToDeploy,
   CyclicDependency(String),
   MissingArtifact(String, String),
+  InvalidArtifact(String, String),
+  AmbiguousArtifact(String, Vec<String>),
+  UnresolvedLibrary(String, String),
+  AmbiguousLibrary(String, Vec<String>),
   MissingABIPath(String),
   MissingBytecodePath(String),
   TooManyConstructorArgs(String),
+  SizeLimitExceeded(String, SizeLimitViolation),
   MissingConfigForReference(String),
   InvalidAddress(String, String),
   Connection(blockchain::error::ConnectionError),
@@ -38,9 +44,14 @@ impl Error for DeploymentError {
       DeploymentError::NothingToDeploy => None,
       DeploymentError::CyclicDependency(_name) => None,
       DeploymentError::MissingArtifact(_kind, _name) => None,
+      DeploymentError::InvalidArtifact(_name, _message) => None,
+      DeploymentError::AmbiguousArtifact(_reference, _candidates) => None,
+      DeploymentError::UnresolvedLibrary(_library, _name) => None,
+      DeploymentError::AmbiguousLibrary(_library, _names) => None,
       DeploymentError::MissingABIPath(_name) => None,
       DeploymentError::MissingBytecodePath(_name) => None,
       DeploymentError::TooManyConstructorArgs(_name) => None,
+      DeploymentError::SizeLimitExceeded(_name, _violation) => None,
       DeploymentError::MissingConfigForReference(_reference) => None,
       DeploymentError::InvalidAddress(_name, _message) => None,
       DeploymentError::Connection(error) => Some(error),
@@ -61,9 +72,18 @@ impl fmt::Display for DeploymentError {
       DeploymentError::NothingToDeploy => write!(f, "Couldn't find artifacts to deploy. Please compile first."),
       DeploymentError::CyclicDependency(name) => write!(f, "Couldn't deploy Smart Contracts due to a cyclic dependency in '{}'", name),
       DeploymentError::MissingArtifact(kind, name) => write!(f, "Couldn't find {} file for artifact '{}'", kind, name),
+      DeploymentError::InvalidArtifact(name, message) => write!(f, "Couldn't read artifact for Smart Contract '{}': {}", name, message),
+      DeploymentError::AmbiguousArtifact(reference, candidates) => {
+        write!(f, "Smart Contract '{}' is ambiguous, use `instance_of` with one of: {}", reference, candidates.join(", "))
+      },
+      DeploymentError::UnresolvedLibrary(library, name) => {
+        write!(f, "Couldn't link library '{}' into Smart Contract '{}'. Libraries have to be deployed in the same run or configured with an `address`.", library, name)
+      },
+      DeploymentError::AmbiguousLibrary(library, names) => write!(f, "Library '{}' is ambiguous, it's deployed by: {}", library, names.join(", ")),
       DeploymentError::MissingABIPath(name) => write!(f, "Missing `abi_path` for Smart Contract configuration '{}'", name),
       DeploymentError::MissingBytecodePath(name) => write!(f, "Missing `bytecode_path` for Smart Contract configuration '{}'", name),
       DeploymentError::TooManyConstructorArgs(name) => write!(f, "Couldn't deploy Smart Contract '{}' due to too many constructor arguments (max. 10)", name),
+      DeploymentError::SizeLimitExceeded(name, violation) => write!(f, "Couldn't deploy Smart Contract '{}', its {}", name, violation),
       DeploymentError::MissingConfigForReference(reference) => write!(f, "Couldn't find Smart Contract configuration for reference '{}'", reference),
       DeploymentError::InvalidAddress(name, message) => write!(f, "Invalid address in Smart Contract configuration for '{}': {}", name, message),
       DeploymentError::Connection(error) => write!(f, "{}", error),
@@ -0,0 +1,151 @@
+use super::artifacts::{self, SOURCE_SEPARATOR};
+use crate::compiler::standard_json::StandardJsonLinkReference;
+
+use sha3::{Digest, Keccak256};
+use std::collections::BTreeMap;
+use std::ops::Range;
+use web3::types::Address;
+
+// Placeholders take the place of a hex encoded address.
+const PLACEHOLDER_LENGTH: usize = 40;
+const PLACEHOLDER_PREFIX: &str = "__";
+const HASH_DELIMITER: char = '$';
+// Hashed placeholders hold the first 17 bytes of the hash of the library's qualified name.
+const PLACEHOLDER_HASH_LENGTH: usize = 34;
+
+/// Positions of library addresses in hex encoded bytecode, by library name. Names are qualified
+/// by the library's source file, e.g. `contracts/Math.sol:Math`, if it's known.
+pub type LinkReferences = BTreeMap<String, Vec<Range<usize>>>;
+
+/// Finds the link references of hex encoded bytecode. Link references reported by the compiler
+/// are used as they are. Other placeholders are either `__<Library>___…`, as written by solc
+/// before 0.5, where the library name may be qualified by its source file, or `__$<Hash>$__`,
+/// which is keyed by `$<Hash>$`, because the library name can't be derived from it, see `links`.
+pub fn find_link_references(bytecode: &str, reported: &BTreeMap<String, Vec<StandardJsonLinkReference>>) -> LinkReferences {
+  let mut link_references = LinkReferences::new();
+  for (library, references) in reported {
+    for reference in references {
+      link_references.entry(library.clone()).or_default().push(reference.start * 2..(reference.start + reference.length) * 2);
+    }
+  }
+
+  let mut position = 0;
+  while let Some(offset) = bytecode[position..].find(PLACEHOLDER_PREFIX) {
+    let start = position + offset;
+    let placeholder = match bytecode.get(start..start + PLACEHOLDER_LENGTH) {
+      Some(placeholder) => placeholder,
+      None => break,
+    };
+    position = start + PLACEHOLDER_LENGTH;
+
+    if link_references.values().flatten().any(|range| range.start == start) {
+      continue;
+    }
+    let library = placeholder.trim_matches('_');
+    link_references.entry(library.to_string()).or_default().push(start..start + PLACEHOLDER_LENGTH);
+  }
+  link_references
+}
+
+/// Whether the link reference of `library` refers to the contract `reference`, which is either
+/// a contract name or qualified by its source file, e.g. `contracts/Math.sol:Math`. Libraries of
+/// hashed placeholders are matched by `compiled_names` instead, the qualified names the contract
+/// may have been compiled as, see `Compiler::qualified_names`.
+pub fn links(library: &str, reference: &str, compiled_names: &[String]) -> bool {
+  if is_hashed(library) {
+    return compiled_names.iter().any(|name| library.trim_matches(HASH_DELIMITER) == placeholder_hash(name));
+  }
+  match (split_qualified_name(library), split_qualified_name(reference)) {
+    ((Some(library_source), library_name), (Some(source), name)) => {
+      library_name == name && artifacts::normalize_source_path(library_source) == artifacts::normalize_source_path(source)
+    },
+    ((_library_source, library_name), (_source, name)) => library_name == name,
+  }
+}
+
+/// Whether the link reference is a hashed placeholder, whose library name is unknown.
+pub fn is_hashed(library: &str) -> bool {
+  library.starts_with(HASH_DELIMITER)
+}
+
+// The hash of a hashed placeholder for the library with the given qualified name.
+fn placeholder_hash(qualified_name: &str) -> String {
+  let hash = format!("{:x}", Keccak256::digest(qualified_name.as_bytes()));
+  hash[..PLACEHOLDER_HASH_LENGTH].to_string()
+}
+
+fn split_qualified_name(name: &str) -> (Option<&str>, &str) {
+  match name.rfind(SOURCE_SEPARATOR) {
+    Some(index) => (Some(&name[..index]), &name[index + 1..]),
+    None => (None, name),
+  }
+}
+
+/// Replaces the link references of the given bytecode with the addresses of their libraries.
+/// Link references of libraries without address are left as they are.
+pub fn link(bytecode: &str, link_references: &LinkReferences, addresses: &BTreeMap<String, Address>) -> String {
+  let mut linked = bytecode.to_string();
+  for (library, ranges) in link_references {
+    if let Some(address) = addresses.get(library) {
+      let address = format!("{:?}", address)[2..].to_owned();
+      for range in ranges {
+        if range.end <= linked.len() {
+          linked.replace_range(range.clone(), &address);
+        }
+      }
+    }
+  }
+  linked
+}
+
+#[cfg(test)]
+mod tests {
+
+  use std::collections::BTreeMap;
+  use std::str::FromStr;
+  use web3::types::Address;
+  use super::{find_link_references, link, links, placeholder_hash};
+  use crate::compiler::standard_json::StandardJsonLinkReference;
+
+  const MATH_ADDRESS: &str = "5aeda56215b167893e80b4fe645ba6d5bab767de";
+
+  #[test]
+  fn it_should_find_and_link_placeholders() {
+    let bytecode = format!("6080{}6040{}", "__contracts/Math.sol:Math_______________", "__Math__________________________________");
+    let link_references = find_link_references(&bytecode, &BTreeMap::new());
+    assert_eq!(link_references.keys().collect::<Vec<&String>>(), vec!["Math", "contracts/Math.sol:Math"]);
+    assert_eq!(link_references["contracts/Math.sol:Math"], vec![4..44]);
+    assert_eq!(link_references["Math"], vec![48..88]);
+
+    let mut addresses = BTreeMap::new();
+    addresses.insert("Math".to_string(), Address::from_str(MATH_ADDRESS).unwrap());
+    addresses.insert("contracts/Math.sol:Math".to_string(), Address::from_str(MATH_ADDRESS).unwrap());
+    assert_eq!(link(&bytecode, &link_references, &addresses), format!("6080{}6040{}", MATH_ADDRESS, MATH_ADDRESS));
+  }
+
+  #[test]
+  fn it_should_match_libraries_by_qualified_names_and_placeholder_hashes() {
+    assert!(links("contracts/Math.sol:Math", "Math", &[]));
+    assert!(links("contracts/Math.sol:Math", "./contracts/Math.sol:Math", &[]));
+    assert!(!links("contracts/Math.sol:Math", "contracts/legacy/Math.sol:Math", &[]));
+    assert!(links("Math", "contracts/legacy/Math.sol:Math", &[]));
+    assert!(!links("Math", "MathV2", &[]));
+
+    assert_eq!(placeholder_hash(""), "c5d2460186f7233c927e7db2dcc703c0e5");
+    let library = format!("${}$", placeholder_hash("/project/contracts/Math.sol:Math"));
+    assert!(links(&library, "Math", &["contracts/Math.sol:Math".to_string(), "/project/contracts/Math.sol:Math".to_string()]));
+    assert!(!links(&library, "Math", &["/project/contracts/legacy/Math.sol:Math".to_string()]));
+    assert!(!links(&library, "/project/contracts/Math.sol:Math", &[]));
+  }
+
+  #[test]
+  fn it_should_prefer_link_references_reported_by_the_compiler() {
+    let bytecode = format!("6080{}6040{}", "__$1234567890abcdef1234567890abcdef12$__", "__$fedcba0987654321fedcba0987654321fe$__");
+    let mut reported = BTreeMap::new();
+    reported.insert("Math".to_string(), vec![StandardJsonLinkReference { start: 2, length: 20 }]);
+    let link_references = find_link_references(&bytecode, &reported);
+
+    assert_eq!(link_references["Math"], vec![4..44]);
+    assert_eq!(link_references["$fedcba0987654321fedcba0987654321fe$"], vec![48..88]);
+  }
+}
@@ -1,9 +1,14 @@
+pub mod artifacts;
 pub mod error;
+pub mod linker;
 pub mod tracker;
 
+use artifacts::{Artifact, ArtifactIndex, IndexedArtifact, SOURCE_SEPARATOR};
 use blockchain::connector::BlockchainConnector;
 use config::{Config, SmartContractConfig, SmartContractArg};
 use crate::blockchain;
+use crate::compiler::Compiler;
+use crate::compiler::sizes::{self, SizeLimits};
 use crate::config;
 use error::DeploymentError;
 use ethabi::{Token, ParamType};
@@ -14,14 +19,12 @@ use petgraph::algo::toposort;
 use std::fs;
 use std::str::FromStr;
 use std::path::PathBuf;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use tracker::DeploymentTracker;
 use web3::contract::Options;
 use web3::futures::Future;
 use web3::types::{U256, H256, Address};
 
-const ARTIFACT_EXTENSION_BINARY: &str = "bin";
-const ARTIFACT_EXTENSION_ABI: &str = "abi";
 const DEFAULT_GAS_PRICE: usize = 5;
 const DEFAULT_GAS_LIMIT: usize = 2_000_000;
 const DEFAULT_DEV_TX_CONFIRMATION_AMOUNT: usize = 0;
@@ -38,6 +41,10 @@ pub struct Deployer<'a> {
 
 pub type DeployedContracts = HashMap<Address, (String, Address, String, bool)>;
 
+// Libraries linked into Smart Contracts, the configuration names of the libraries by library name
+// for each Smart Contract configuration name.
+type LibraryDependencies<'a> = HashMap<&'a str, BTreeMap<String, &'a str>>;
+
 impl<'a> Deployer<'a> {
   pub fn new(config: &'a Config, connector: &'a BlockchainConnector, tracker: &'a DeploymentTracker) -> Deployer<'a> {
     Deployer {
@@ -55,6 +62,7 @@ impl<'a> Deployer<'a> {
       return Err(DeploymentError::MissingConfig);
     }
 
+    let size_limits = SizeLimits::from_config(project_config.compiler.as_ref());
     let deployment_config = &project_config.deployment.unwrap();
     let accounts = self.connector.accounts()?;
 
@@ -71,7 +79,16 @@ impl<'a> Deployer<'a> {
       self.tracker.create_database()?;
     }
 
-    let sorted_smart_contract_configs = sort_by_dependencies(&deployment_config.smart_contracts)?;
+    let artifact_index = ArtifactIndex::read(&self.config.project_path.join(&project_config.sources.artifacts))?;
+    let mut artifacts = HashMap::new();
+    for smart_contract_config in deployment_config.smart_contracts.iter().filter(|config| config.address.is_none()) {
+      if let Some(artifact) = self.get_artifacts(&artifact_index, smart_contract_config)? {
+        artifacts.insert(smart_contract_config.name.as_str(), artifact);
+      }
+    }
+
+    let libraries = library_dependencies(&deployment_config.smart_contracts, &artifacts, |reference| compiled_names(self.config, &artifact_index, reference))?;
+    let sorted_smart_contract_configs = sort_by_dependencies(&deployment_config.smart_contracts, &libraries)?;
 
     for smart_contract_config in sorted_smart_contract_configs {
 
@@ -82,10 +99,16 @@ impl<'a> Deployer<'a> {
         continue;
       }
 
-      if let Some((bin_path, abi_path)) = self.get_artifacts(&project_config.sources.artifacts, smart_contract_config)? {
+      if let Some(artifact) = artifacts.remove(smart_contract_config.name.as_str()) {
 
-        let bytecode = fs::read_to_string(&bin_path).unwrap();
-        let abi = fs::read(abi_path).unwrap();
+        let Artifact { path: artifact_path, abi, bytecode, link_references, runtime_size } = artifact;
+        let bytecode = match libraries.get(smart_contract_config.name.as_str()) {
+          Some(contract_libraries) => {
+            let addresses = library_addresses(&smart_contract_config.name, contract_libraries, &deployed_contracts)?;
+            linker::link(&bytecode, &link_references, &addresses)
+          },
+          None => bytecode,
+        };
 
         let args = smart_contract_config.args.as_ref().unwrap_or(&vec![]).iter().map(|arg| arg.value.clone()).collect::<Vec<String>>();
 
@@ -100,11 +123,13 @@ impl<'a> Deployer<'a> {
 
           if let Some(tracked_contract) = tracked_contract {
             info!("{} is already deployed at {:?}", &tracked_contract.name, &tracked_contract.address);
-            deployed_contracts.insert(tracked_contract.address, (tracked_contract.name, tracked_contract.address, bin_path.to_string_lossy().to_string(), true));
+            deployed_contracts.insert(tracked_contract.address, (tracked_contract.name, tracked_contract.address, artifact_path.to_string_lossy().to_string(), true));
             continue;
           }
         }
 
+        check_size_limits(&smart_contract_config.name, runtime_size, &bytecode, &tokenized_args, &size_limits)?;
+
         info!("Deploying {}...", &smart_contract_config.name);
 
         let builder = self.connector.deploy(&abi)?;
@@ -130,7 +155,7 @@ impl<'a> Deployer<'a> {
         }
 
         info!("Deployed {} at {:?}", &smart_contract_config.name, &contract.address());
-        deployed_contracts.insert(contract.address(), (smart_contract_config.name.to_owned(), contract.address(), bin_path.to_string_lossy().to_string(), false));
+        deployed_contracts.insert(contract.address(), (smart_contract_config.name.to_owned(), contract.address(), artifact_path.to_string_lossy().to_string(), false));
       } else {
         warn!("No bytecode or ABI found for Smart Contract '{}'", &smart_contract_config.name);
       }
@@ -138,7 +163,9 @@ impl<'a> Deployer<'a> {
     Ok(deployed_contracts)
   }
 
-  fn get_artifacts(&self, artifacts_path: &str, config: &SmartContractConfig) -> Result<Option<(PathBuf, PathBuf)>, DeploymentError> {
+  // Looks up the artifacts of a Smart Contract. Pre-defined `abi_path` and `bytecode_path` take
+  // precedence over the artifact of the contract referenced by `instance_of` or `name`.
+  fn get_artifacts(&self, artifact_index: &ArtifactIndex, config: &SmartContractConfig) -> Result<Option<Artifact>, DeploymentError> {
     if config.bytecode_path.is_some() && config.abi_path.is_none() {
       Err(DeploymentError::MissingABIPath(config.name.to_string()))
     } else if config.bytecode_path.is_none() && config.abi_path.is_some() {
@@ -147,30 +174,13 @@ impl<'a> Deployer<'a> {
       let bytecode_path = self.config.project_path.join(&PathBuf::from(config.bytecode_path.as_ref().unwrap()));
       let abi_path = self.config.project_path.join(&PathBuf::from(config.abi_path.as_ref().unwrap()));
       info!("Using pre-defined artifacts: {:?} and {:?}", &abi_path, &bytecode_path);
-      Ok(Some((bytecode_path, abi_path)))
+      Ok(Some(Artifact::new(bytecode_path.clone(), fs::read(&abi_path)?, fs::read_to_string(&bytecode_path)?)))
     } else {
-      let artifacts_path = self.config.project_path.join(artifacts_path);
-      let artifacts_dir = std::fs::read_dir(&artifacts_path)?;
-      let artifact_names: Vec<PathBuf> = artifacts_dir.map(|res| res.unwrap().path()).collect();
       let smart_contract_name = config.instance_of.as_ref().unwrap_or(&config.name);
-
-      if let Some(artifact) = artifact_names.iter().find(|path| path.to_string_lossy().to_string().contains(smart_contract_name)) {
-        let file_extension = &artifact.extension().unwrap().to_str().unwrap();
-
-        if file_extension == &ARTIFACT_EXTENSION_BINARY || file_extension == &ARTIFACT_EXTENSION_ABI {
-
-          let file_bin_path = PathBuf::from(&artifact).with_extension(&ARTIFACT_EXTENSION_BINARY);
-          let file_abi_path = PathBuf::from(&artifact).with_extension(&ARTIFACT_EXTENSION_ABI);
-
-          if file_extension == &ARTIFACT_EXTENSION_BINARY && !file_abi_path.exists() {
-            return Err(DeploymentError::MissingArtifact(ARTIFACT_EXTENSION_ABI.to_string(), file_bin_path.to_string_lossy().to_string()));
-          } else if file_extension == &ARTIFACT_EXTENSION_ABI && !file_bin_path.exists() {
-            return Err(DeploymentError::MissingArtifact(ARTIFACT_EXTENSION_BINARY.to_string(), file_abi_path.to_string_lossy().to_string()));
-          }
-          return Ok(Some((file_bin_path, file_abi_path)));
-        }
+      match artifact_index.find(smart_contract_name)? {
+        Some(artifact) => artifact.load().map(Some),
+        None => Ok(None),
       }
-      Ok(None)
     }
   }
 
@@ -180,6 +190,16 @@ impl<'a> Deployer<'a> {
   }
 }
 
+// Deployments of Smart Contracts exceeding the size limits of the chain fail after spending gas,
+// so they're checked before the transaction is sent.
+fn check_size_limits(name: &str, runtime_size: Option<usize>, bytecode: &str, tokenized_args: &[Token], size_limits: &SizeLimits) -> Result<(), DeploymentError> {
+  let initcode_size = sizes::bytecode_size(bytecode).map(|size| size + ethabi::encode(tokenized_args).len());
+  match size_limits.check(runtime_size, initcode_size).into_iter().next() {
+    Some(violation) => Err(DeploymentError::SizeLimitExceeded(name.to_string(), violation)),
+    None => Ok(()),
+  }
+}
+
 fn tokenize_args(args: &[SmartContractArg], deployed_contracts: &HashMap<Address, (String, Address, String, bool)>) -> Result<Vec<Token>, DeploymentError> {
   let mut tokenized_args: Vec<Token> = vec![];
 
@@ -204,14 +224,81 @@ fn tokenize_args(args: &[SmartContractArg], deployed_contracts: &HashMap<Address
   Ok(tokenized_args)
 }
 
-fn sort_by_dependencies(smart_contracts: &[SmartContractConfig]) -> Result<Vec<&SmartContractConfig>, DeploymentError> {
-  let graph = DiGraphMap::<&str, ()>::from_edges(
-    smart_contracts.iter().filter(|contract| contract.args.is_some()).flat_map(|contract| {
-      contract.args.as_ref().unwrap().iter()
-        .filter(|dep| dep.value.starts_with('$') && dep.kind == "address")
-        .map(move |dep| (contract.name.as_str(), &dep.value[1..]))
+// Returns the qualified names the contract referred to by `reference` may have been compiled as,
+// see `linker::links`. Contracts that aren't referred to by qualified names are looked up in the
+// artifacts. If their source file is unknown, e.g. because they've been compiled with
+// `compiler.options`, all source files of the project are considered.
+fn compiled_names(config: &Config, artifact_index: &ArtifactIndex, reference: &str) -> Result<Vec<String>, DeploymentError> {
+  let compiler = Compiler::new(config);
+  if let Some(index) = reference.rfind(SOURCE_SEPARATOR) {
+    return Ok(compiler.qualified_names(&artifacts::normalize_source_path(&reference[..index]), &reference[index + 1..]));
+  }
+  match artifact_index.find(reference).ok().and_then(|artifact| artifact) {
+    Some(IndexedArtifact { source_path: Some(source_path), contract_name, .. }) => Ok(compiler.qualified_names(source_path, contract_name)),
+    _ => {
+      let source_paths = compiler.source_paths().map_err(|err| DeploymentError::Other(err.to_string()))?;
+      Ok(source_paths.iter().flat_map(|source_path| compiler.qualified_names(source_path, reference)).collect())
+    },
+  }
+}
+
+// Resolves the libraries linked into each Smart Contract to the configurations that deploy them or
+// specify their `address`. Libraries are matched by the contract their configuration refers to,
+// see `linker::links`. `compiled_names` returns the qualified names of a referenced contract, which
+// are only looked up for hashed placeholders.
+fn library_dependencies<'a, F>(smart_contracts: &'a [SmartContractConfig], artifacts: &HashMap<&'a str, Artifact>, compiled_names: F) -> Result<LibraryDependencies<'a>, DeploymentError>
+  where F: Fn(&str) -> Result<Vec<String>, DeploymentError>
+{
+  let mut dependencies = LibraryDependencies::new();
+  let mut compiled_names_by_reference: HashMap<&str, Vec<String>> = HashMap::new();
+  for (name, artifact) in artifacts {
+    for library in artifact.link_references.keys() {
+      let mut candidates = vec![];
+      for config in smart_contracts {
+        let reference = config.instance_of.as_ref().unwrap_or(&config.name).as_str();
+        if linker::is_hashed(library) && !compiled_names_by_reference.contains_key(reference) {
+          compiled_names_by_reference.insert(reference, compiled_names(reference)?);
+        }
+        let names = compiled_names_by_reference.get(reference).map(Vec::as_slice).unwrap_or(&[]);
+        if linker::links(library, reference, names) {
+          candidates.push(config);
+        }
+      }
+
+      match candidates.len() {
+        0 => return Err(DeploymentError::UnresolvedLibrary(library.to_owned(), name.to_string())),
+        1 => {
+          dependencies.entry(*name).or_default().insert(library.to_owned(), candidates[0].name.as_str());
+        },
+        _ => return Err(DeploymentError::AmbiguousLibrary(library.to_owned(), candidates.iter().map(|config| config.name.to_owned()).collect())),
+      }
+    }
+  }
+  Ok(dependencies)
+}
+
+// Looks up the addresses of the libraries of a Smart Contract, which have been deployed before.
+fn library_addresses(name: &str, libraries: &BTreeMap<String, &str>, deployed_contracts: &DeployedContracts) -> Result<BTreeMap<String, Address>, DeploymentError> {
+  libraries.iter()
+    .map(|(library, config_name)| {
+      deployed_contracts.values()
+        .find(|contract| contract.0 == *config_name)
+        .map(|contract| (library.to_owned(), contract.1))
+        .ok_or_else(|| DeploymentError::UnresolvedLibrary(library.to_owned(), name.to_owned()))
     })
-  ).into_graph::<u32>();
+    .collect()
+}
+
+fn sort_by_dependencies<'a>(smart_contracts: &'a [SmartContractConfig], libraries: &LibraryDependencies<'a>) -> Result<Vec<&'a SmartContractConfig>, DeploymentError> {
+  let argument_dependencies = smart_contracts.iter().filter(|contract| contract.args.is_some()).flat_map(|contract| {
+    contract.args.as_ref().unwrap().iter()
+      .filter(|dep| dep.value.starts_with('$') && dep.kind == "address")
+      .map(move |dep| (contract.name.as_str(), &dep.value[1..]))
+  });
+  let library_dependencies = libraries.iter().flat_map(|(name, contract_libraries)| {
+    contract_libraries.values().map(move |library| (*name, *library))
+  });
+  let graph = DiGraphMap::<&str, ()>::from_edges(argument_dependencies.chain(library_dependencies)).into_graph::<u32>();
 
   let sorted_names = toposort(&graph, None).map_err(|err| DeploymentError::CyclicDependency(graph[err.node_id()].to_string()))?;
   let mut smart_contract_map = HashMap::new();
@@ -241,6 +328,7 @@ mod tests {
 
   mod sort_by_dependencies {
 
+    use std::collections::{BTreeMap, HashMap};
     use super::super::sort_by_dependencies;
     use crate::config::ProjectConfig;
 
@@ -267,7 +355,7 @@ mod tests {
       ").unwrap();
 
       let smart_contracts = project_config.deployment.unwrap().smart_contracts;
-      assert_eq!(sort_by_dependencies(&smart_contracts).is_err(), true);
+      assert_eq!(sort_by_dependencies(&smart_contracts, &HashMap::new()).is_err(), true);
     }
 
     #[test]
@@ -289,7 +377,7 @@ mod tests {
       ").unwrap();
 
       let smart_contracts = project_config.deployment.unwrap().smart_contracts;
-      assert_eq!(sort_by_dependencies(&smart_contracts).is_err(), true);
+      assert_eq!(sort_by_dependencies(&smart_contracts, &HashMap::new()).is_err(), true);
     }
 
     #[test]
@@ -321,10 +409,158 @@ mod tests {
 
       let smart_contracts = project_config.deployment.unwrap().smart_contracts;
 
-      let sorted = sort_by_dependencies(&smart_contracts).unwrap();
+      let sorted = sort_by_dependencies(&smart_contracts, &HashMap::new()).unwrap();
       let expected = vec!["D", "B", "C", "A"];
 
       assert_eq!(sorted.iter().map(|contract| contract.name.as_str()).collect::<Vec<&str>>(), expected);
     }
+
+    #[test]
+    fn it_should_sort_libraries_before_smart_contracts_linking_them() {
+      let project_config = project_config_from_string("
+        [sources]
+          artifacts = \"artifacts\"
+          smart_contracts = [\"contracts/*.sol\"]
+        [[deployment.smart_contracts]]
+          name = \"Token\"
+        [[deployment.smart_contracts]]
+          name = \"Math\"
+      ").unwrap();
+
+      let smart_contracts = project_config.deployment.unwrap().smart_contracts;
+      let mut token_libraries = BTreeMap::new();
+      token_libraries.insert("Math".to_string(), "Math");
+      let mut libraries = HashMap::new();
+      libraries.insert("Token", token_libraries);
+
+      let sorted = sort_by_dependencies(&smart_contracts, &libraries).unwrap();
+      assert_eq!(sorted.iter().map(|contract| contract.name.as_str()).collect::<Vec<&str>>(), vec!["Math", "Token"]);
+    }
+  }
+
+  mod library_dependencies {
+
+    extern crate tempfile;
+
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::PathBuf;
+    use super::super::artifacts::{Artifact, ArtifactIndex};
+    use sha3::{Digest, Keccak256};
+    use tempfile::tempdir;
+    use super::super::{compiled_names, library_dependencies};
+    use crate::config::{Config, ProjectConfig};
+    use crate::utils::adjust_canonicalization;
+
+    // A hashed placeholder as written by solc for the library with the given qualified name.
+    fn hashed_placeholder(qualified_name: &str) -> String {
+      format!("__${}$__", &format!("{:x}", Keccak256::digest(qualified_name.as_bytes()))[..34])
+    }
+
+    #[test]
+    fn it_should_resolve_libraries_to_smart_contract_configurations() {
+      let project_config: ProjectConfig = toml::from_str("
+        [sources]
+          artifacts = \"artifacts\"
+          smart_contracts = [\"contracts/*.sol\"]
+        [[deployment.smart_contracts]]
+          name = \"Token\"
+        [[deployment.smart_contracts]]
+          name = \"SafeMath\"
+          instance_of = \"contracts/Math.sol:Math\"
+        [[deployment.smart_contracts]]
+          name = \"Sale\"
+      ").unwrap();
+      let smart_contracts = project_config.deployment.unwrap().smart_contracts;
+
+      let mut artifacts = HashMap::new();
+      artifacts.insert("Token", Artifact::new(PathBuf::from("Token.bin"), vec![], format!("6080{}", "__Math__________________________________")));
+      let libraries = library_dependencies(&smart_contracts, &artifacts, |_reference| Ok(vec![])).unwrap();
+      assert_eq!(libraries["Token"]["Math"], "SafeMath");
+
+      artifacts.insert("Sale", Artifact::new(PathBuf::from("Sale.bin"), vec![], format!("6080{}", "__Vault_________________________________")));
+      assert!(library_dependencies(&smart_contracts, &artifacts, |_reference| Ok(vec![])).is_err());
+    }
+
+    #[test]
+    fn it_should_resolve_hashed_placeholders_by_the_paths_passed_to_the_compiler() -> Result<(), Box<dyn std::error::Error>> {
+      let tmp_dir = tempdir()?;
+      let project_path = adjust_canonicalization(tmp_dir.path().canonicalize()?);
+      let config = Config::new(project_path.clone());
+      fs::create_dir_all(project_path.join("contracts").join("legacy"))?;
+      fs::create_dir_all(project_path.join("artifacts"))?;
+      fs::write(&config.config_file, "
+        [sources]
+          artifacts = \"artifacts\"
+          smart_contracts = [\"contracts/**/*.sol\"]
+        [[deployment.smart_contracts]]
+          name = \"Token\"
+        [[deployment.smart_contracts]]
+          name = \"Sale\"
+        [[deployment.smart_contracts]]
+          name = \"Math\"
+        [[deployment.smart_contracts]]
+          name = \"LegacyVault\"
+          instance_of = \"contracts/legacy/Vault.sol:Vault\"
+      ")?;
+      fs::write(project_path.join("contracts").join("Math.sol"), "library Math {}")?;
+      fs::write(project_path.join("contracts").join("legacy").join("Vault.sol"), "library Vault {}")?;
+      fs::write(project_path.join("contracts").join("Token.sol"), "import \"./Math.sol\"; contract Token {}")?;
+      // Libraries compiled with `compiler.options` only have `.abi` and `.bin` artifacts.
+      fs::write(project_path.join("artifacts").join("Math.abi"), "[]")?;
+      fs::write(project_path.join("artifacts").join("Math.bin"), "6080")?;
+
+      let smart_contracts = config.read()?.deployment.unwrap().smart_contracts;
+      let artifact_index = ArtifactIndex::read(&project_path.join("artifacts"))?;
+
+      // Solc hashes the qualified names of the source files it's given: absolute paths, or the
+      // paths of normalized copies in `.vibranium` with smart imports.
+      let math_path = project_path.join("contracts").join("Math.sol");
+      let vault_path = config.vibranium_dir_path.join("contracts").join("legacy").join("Vault.sol");
+      let mut artifacts = HashMap::new();
+      artifacts.insert("Token", Artifact::new(PathBuf::from("Token.bin"), vec![], format!("6080{}", hashed_placeholder(&format!("{}:Math", math_path.to_string_lossy())))));
+      artifacts.insert("Sale", Artifact::new(PathBuf::from("Sale.bin"), vec![], format!("6080{}", hashed_placeholder(&format!("{}:Vault", vault_path.to_string_lossy())))));
+
+      let libraries = library_dependencies(&smart_contracts, &artifacts, |reference| compiled_names(&config, &artifact_index, reference))?;
+      assert_eq!(libraries["Token"].values().collect::<Vec<&&str>>(), vec![&"Math"]);
+      assert_eq!(libraries["Sale"].values().collect::<Vec<&&str>>(), vec![&"LegacyVault"]);
+
+      // Placeholders of names the compiler wasn't given don't resolve.
+      artifacts.insert("Token", Artifact::new(PathBuf::from("Token.bin"), vec![], format!("6080{}", hashed_placeholder("Math.sol:Math"))));
+      assert!(library_dependencies(&smart_contracts, &artifacts, |reference| compiled_names(&config, &artifact_index, reference)).is_err());
+
+      tmp_dir.close()?;
+      Ok(())
+    }
+  }
+
+  mod check_size_limits {
+
+    use ethabi::Token;
+    use web3::types::U256;
+    use super::super::check_size_limits;
+    use crate::compiler::sizes::{SizeKind, SizeLimits};
+    use crate::deployment::error::DeploymentError;
+
+    #[test]
+    fn it_should_fail_before_deploying_smart_contracts_exceeding_size_limits() {
+      let size_limits = SizeLimits { max_contract_size: 64, max_initcode_size: 64, ..SizeLimits::default() };
+      let bytecode = "00".repeat(60);
+      assert!(check_size_limits("Token", Some(32), &bytecode, &[], &size_limits).is_ok());
+
+      match check_size_limits("Token", Some(32), &bytecode, &[Token::Uint(U256::from(100))], &size_limits) {
+        Err(DeploymentError::SizeLimitExceeded(name, violation)) => {
+          assert_eq!(name, "Token");
+          assert_eq!(violation.kind, SizeKind::Initcode);
+          assert_eq!(violation.size, 92);
+        },
+        _ => panic!("Expected size limit error"),
+      }
+
+      match check_size_limits("Token", Some(65), "6080", &[], &size_limits) {
+        Err(DeploymentError::SizeLimitExceeded(_name, violation)) => assert_eq!(violation.kind, SizeKind::Runtime),
+        _ => panic!("Expected size limit error"),
+      }
+    }
   }
 }
@@ -2,6 +2,8 @@
 extern crate serde_derive;
 #[macro_use]
 extern crate log;
+#[macro_use]
+extern crate lazy_static;
 extern crate glob;
 extern crate web3;
 extern crate ethabi;
@@ -10,6 +12,7 @@ extern crate regex;
 extern crate sha3;
 extern crate toml;
 extern crate toml_query;
+extern crate toml_edit;
 
 pub mod accounts_manager;
 pub mod blockchain;
@@ -19,8 +22,9 @@ pub mod config;
 pub mod deployment;
 mod utils;
 
-use std::process::{ExitStatus, Output};
-use std::path::PathBuf;
+use std::process::ExitStatus;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use blockchain::connector as connector;
 use project_generator::error::ProjectGenerationError;
 use utils::adjust_canonicalization;
@@ -30,6 +34,7 @@ use web3::types::Address;
 pub struct Vibranium {
   project_path: PathBuf,
   pub config: config::Config,
+  compiler_strategies: compiler::strategy::CompilerStrategies,
 }
 
 impl Vibranium {
@@ -39,9 +44,36 @@ impl Vibranium {
     Ok(Vibranium {
       config: config::Config::new(project_path.clone()),
       project_path,
+      compiler_strategies: compiler::strategy::CompilerStrategies::default(),
     })
   }
 
+  /// Creates an instance for the project that contains `start_path`. Similar to git, `start_path`
+  /// and its parent directories are searched for a `vibranium.toml` file or `.vibranium` directory.
+  pub fn discover(start_path: PathBuf) -> Result<Vibranium, ProjectGenerationError> {
+    let start_path = start_path.canonicalize().map_err(|_err| ProjectGenerationError::ProjectPathNotFound)?;
+    let start_path = adjust_canonicalization(&start_path);
+
+    let home_path = utils::home_dir()
+      .and_then(|home| home.canonicalize().ok())
+      .map(adjust_canonicalization);
+    let project_path = find_project_path(&start_path, home_path.as_deref())
+      .ok_or(ProjectGenerationError::VibraniumDirectoryNotFound)?;
+
+    info!("Found Vibranium project at: {:?}", &project_path);
+    Vibranium::new(project_path.to_path_buf())
+  }
+
+  pub fn set_environment(&mut self, environment: String) {
+    self.config.environment = Some(environment);
+  }
+
+  /// Registers a strategy to compile with. It replaces built-in or previously registered
+  /// strategies with the same name.
+  pub fn register_compiler_strategy<S: compiler::strategy::CompilerStrategy + 'static>(&mut self, strategy: S) {
+    self.compiler_strategies.register(Arc::new(strategy));
+  }
+
   pub fn start_node(&self, config: blockchain::NodeConfig) -> Result<ExitStatus, blockchain::error::NodeError> {
     let generator = project_generator::ProjectGenerator::new(&self.config);
     generator
@@ -67,6 +99,38 @@ impl Vibranium {
       .and_then(|_| generator.generate_project(&self.project_path))
   }
 
+  pub fn get_config(&self, option: String) -> Result<Option<toml::Value>, config::error::ConfigError> {
+    let generator = project_generator::ProjectGenerator::new(&self.config);
+    generator
+      .check_vibranium_dir_exists()
+      .map_err(|error| config::error::ConfigError::Other(error.to_string()))
+      .and_then(|_| self.config.get(option))
+  }
+
+  pub fn list_config(&self) -> Result<Vec<(String, toml::Value)>, config::error::ConfigError> {
+    let generator = project_generator::ProjectGenerator::new(&self.config);
+    generator
+      .check_vibranium_dir_exists()
+      .map_err(|error| config::error::ConfigError::Other(error.to_string()))
+      .and_then(|_| self.config.list())
+  }
+
+  pub fn check_config(&self) -> Result<Vec<config::ConfigDiagnostic>, config::error::ConfigError> {
+    let generator = project_generator::ProjectGenerator::new(&self.config);
+    generator
+      .check_vibranium_dir_exists()
+      .map_err(|error| config::error::ConfigError::Other(error.to_string()))
+      .and_then(|_| self.config.validate())
+  }
+
+  pub fn migrate_config(&self) -> Result<Option<config::MigrationSummary>, config::error::ConfigError> {
+    let generator = project_generator::ProjectGenerator::new(&self.config);
+    generator
+      .check_vibranium_dir_exists()
+      .map_err(|error| config::error::ConfigError::Other(error.to_string()))
+      .and_then(|_| self.config.migrate())
+  }
+
   pub fn set_config(&self, option: String, value: toml::Value) -> Result<(), config::error::ConfigError> {
     let generator = project_generator::ProjectGenerator::new(&self.config);
     generator
@@ -83,24 +147,39 @@ impl Vibranium {
       .and_then(|_| self.config.remove(option))
   }
 
-  pub fn compile(&self, config: compiler::CompilerConfig) -> Result<Output, compiler::error::CompilerError> {
+  pub fn compile(&self, config: compiler::CompilerConfig) -> Result<compiler::CompilerOutput, compiler::error::CompilerError> {
+    let compiler = compiler::Compiler::with_strategies(&self.config, self.compiler_strategies.clone());
+    let generator = project_generator::ProjectGenerator::new(&self.config);
+
+    generator
+      .check_vibranium_dir_exists()
+      .map_err(compiler::error::CompilerError::VibraniumDirectoryNotFound)
+      .and_then(|_| compiler.compile(config))
+  }
+
+  /// Compiles the project and recompiles it whenever a source file or one of its imports
+  /// changes, see `Compiler::watch`.
+  pub fn watch_compile<F>(&self, config: compiler::CompilerConfig, watch_config: &compiler::watch::WatchConfig, on_compile: F) -> Result<(), compiler::error::CompilerError>
+    where F: FnMut(Result<compiler::CompilerOutput, compiler::error::CompilerError>) -> bool
+  {
+    let compiler = compiler::Compiler::with_strategies(&self.config, self.compiler_strategies.clone());
+    let generator = project_generator::ProjectGenerator::new(&self.config);
+
+    generator
+      .check_vibranium_dir_exists()
+      .map_err(compiler::error::CompilerError::VibraniumDirectoryNotFound)
+      .and_then(|_| compiler.watch(config, watch_config, on_compile))
+  }
+
+  /// Reads the JSON artifacts of the project's compiled Smart Contracts, see `compiler::artifact::ContractArtifact`.
+  pub fn read_artifacts(&self) -> Result<Vec<compiler::artifact::ContractArtifact>, compiler::error::CompilerError> {
     let compiler = compiler::Compiler::new(&self.config);
     let generator = project_generator::ProjectGenerator::new(&self.config);
 
     generator
       .check_vibranium_dir_exists()
       .map_err(compiler::error::CompilerError::VibraniumDirectoryNotFound)
-      .and_then(|_| compiler.compile(config).map(|process| {
-        process.wait_with_output().map_err(compiler::error::CompilerError::Io)
-      }))
-      .and_then(|output| output)
-      .and_then(|output| {
-        if !output.status.success() {
-          Err(compiler::error::CompilerError::Other(String::from_utf8_lossy(&output.stderr).to_string()))
-        } else {
-          Ok(output)
-        }
-      })
+      .and_then(|_| compiler.read_artifacts())
   }
 
   pub fn get_blockchain_connector(&self) -> Result<(web3::transports::EventLoopHandle, connector::BlockchainConnector), blockchain::error::ConnectionError> {
@@ -126,6 +205,9 @@ impl Vibranium {
   }
 
   pub fn deploy(&self, options: deployment::DeployOptions) -> Result<deployment::DeployedContracts, deployment::error::DeploymentError> {
+    for warning in self.config.validate()? {
+      warn!("{}", warning);
+    }
     let (_eloop, connector) = self.get_blockchain_connector().map_err(deployment::error::DeploymentError::Connection)?;
     let tracker = deployment::tracker::DeploymentTracker::new(&self.config);
     let deployer = deployment::Deployer::new(&self.config, &connector, &tracker);
@@ -140,3 +222,70 @@ impl Vibranium {
       .and_then(|block| tracker.get_all_smart_contract_tracking_data(&block.unwrap().hash.unwrap()))
   }
 }
+
+// The `.vibranium` directory in the home directory holds installed compilers, it doesn't
+// make the home directory a project unless it contains a `vibranium.toml` file.
+fn find_project_path<'a>(start_path: &'a Path, home_path: Option<&Path>) -> Option<&'a Path> {
+  start_path.ancestors()
+    .find(|path| {
+      path.join(config::VIBRANIUM_CONFIG_FILE).exists() ||
+        (path.join(project_generator::VIBRANIUM_PROJECT_DIRECTORY).is_dir() && Some(*path) != home_path)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+  mod discover {
+
+    extern crate tempfile;
+
+    use std::fs;
+    use tempfile::tempdir;
+    use super::super::{find_project_path, Vibranium};
+    use super::super::utils::adjust_canonicalization;
+
+    #[test]
+    fn it_should_find_project_in_parent_directories() -> Result<(), Box<dyn std::error::Error>> {
+      let tmp_dir = tempdir()?;
+      let project_path = adjust_canonicalization(tmp_dir.path().canonicalize()?).join("test_dapp");
+      let start_path = project_path.join("contracts").join("sub");
+
+      fs::create_dir_all(&start_path)?;
+      fs::File::create(project_path.join("vibranium.toml"))?;
+
+      let vibranium = Vibranium::discover(start_path)?;
+      assert_eq!(vibranium.config.project_path, project_path);
+
+      tmp_dir.close()?;
+      Ok(())
+    }
+
+    #[test]
+    fn it_should_fail_if_no_project_is_found() -> Result<(), Box<dyn std::error::Error>> {
+      let tmp_dir = tempdir()?;
+      let start_path = tmp_dir.path().join("not_a_dapp");
+      fs::create_dir_all(&start_path)?;
+
+      assert!(Vibranium::discover(start_path).is_err());
+
+      tmp_dir.close()?;
+      Ok(())
+    }
+
+    #[test]
+    fn it_should_not_treat_vibranium_directory_in_home_as_project() -> Result<(), Box<dyn std::error::Error>> {
+      let tmp_dir = tempdir()?;
+      let home_path = adjust_canonicalization(tmp_dir.path().canonicalize()?);
+      let start_path = home_path.join("not_a_dapp");
+      fs::create_dir_all(home_path.join(".vibranium").join("compilers"))?;
+      fs::create_dir_all(&start_path)?;
+
+      assert_eq!(find_project_path(&start_path, Some(&home_path)), None);
+      assert_eq!(find_project_path(&start_path, None), Some(home_path.as_path()));
+
+      tmp_dir.close()?;
+      Ok(())
+    }
+  }
+}
@@ -37,6 +37,14 @@ pub fn merge_cli_options(a: Vec<String>, b: Vec<String>) -> Vec<String> {
   merged
 }
 
+/// Returns the user's home directory, based on `HOME` or `USERPROFILE` on Windows.
+pub fn home_dir() -> Option<PathBuf> {
+  let variable = if cfg!(target_os = "windows") { "USERPROFILE" } else { "HOME" };
+  std::env::var_os(variable)
+    .filter(|home| !home.is_empty())
+    .map(PathBuf::from)
+}
+
 pub fn normalize_localhost(host: String) -> String {
   match host.as_ref() {
     LOCALHOST_ADDRESS | LOCALHOST_ALIAS => LOCALHOST_ADDRESS.to_owned(),